@@ -1,7 +1,6 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use serde_json::{ Value, json };
 use std::fs::File;
-use std::sync::Arc;
 use polars::prelude::*;
 
 use xtract::loaders::dataframe::NcodeDataFrame;
@@ -16,7 +15,7 @@ fn xtract_profile(c: &mut Criterion) {
         .has_header(true)
         .finish().unwrap();
 
-    let dataframe = NcodeDataFrame { dataframe: Arc::new(df) };
+    let dataframe = NcodeDataFrame::new(df);
     let profile = dataframe.profile();
     // Convert to string and print
     let profile_str = serde_json::to_string_pretty(&profile).unwrap();
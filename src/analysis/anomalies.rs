@@ -0,0 +1,292 @@
+// Isolation forest (Liu, Ting, Zhou 2008) over the numeric and categorical columns of a dataframe
+
+use arrow::datatypes::DataType;
+use polars::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::loaders::dataframe::{is_temporal, ColumnFeatures, DataFrameMeta, NcodeDataFrame, NumericFeatures};
+use crate::loaders::error::Result as FrameResult;
+
+const EULER_GAMMA: f64 = 0.577_215_664_9;
+
+/// Rows of a dataframe encoded as numeric features
+///
+pub struct Encoded {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+/// Encode each row with the profile of its dataframe
+/// Numeric columns are standardized with the profiled mean and std (nulls become the mean),
+/// categorical columns are replaced by the relative frequency of their value.
+/// Other string columns (ids, free text) are skipped.
+///
+pub fn encode(dataframe: &NcodeDataFrame, profile: &DataFrameMeta) -> FrameResult<Encoded> {
+    let nrows = dataframe.dataframe.height();
+    let mut columns: Vec<String> = vec![];
+    let mut features: Vec<Vec<f64>> = vec![];
+
+    for series in dataframe.dataframe.get_columns() {
+        let meta = match profile.column(series.name()) {
+            Some(meta) => meta,
+            None => continue,
+        };
+
+        match (series.dtype(), meta.features()) {
+            // dates and timestamps have a range but no mean or std to standardize with
+            (DataType::Int64, ColumnFeatures::Numeric(numeric))
+            | (DataType::Float64, ColumnFeatures::Numeric(numeric))
+                if !meta.dtype().map_or(false, is_temporal) =>
+            {
+                let std = numeric.std().filter(|std| *std > 0.0).unwrap_or(1.0);
+                let mean = numeric.mean().unwrap_or(0.0);
+                let values = NumericFeatures::get_values(series)?
+                    .into_iter()
+                    .map(|v| v.map(|v| (v - mean) / std).unwrap_or(0.0))
+                    .collect();
+                features.push(values);
+                columns.push(series.name().to_string());
+            }
+
+            (DataType::Utf8, _) if meta.is_categorical() => {
+                let values: Vec<Option<&str>> = series
+                    .utf8()?
+                    .into_iter()
+                    .collect();
+                let mut frequencies: HashMap<Option<&str>, usize> = HashMap::new();
+                for value in values.iter() {
+                    *frequencies.entry(*value).or_insert(0) += 1;
+                }
+                let values = values
+                    .iter()
+                    .map(|v| frequencies[v] as f64 / nrows as f64)
+                    .collect();
+                features.push(values);
+                columns.push(series.name().to_string());
+            }
+
+            _ => {}
+        }
+    }
+
+    // transpose to row major
+    let rows = (0..nrows)
+        .map(|i| features.iter().map(|column| column[i]).collect())
+        .collect();
+
+    Ok(Encoded { columns, rows })
+}
+
+/// Average path length of an unsuccessful search in a binary search tree of n nodes
+///
+fn average_path_length(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        n => {
+            let n = n as f64;
+            2.0 * ((n - 1.0).ln() + EULER_GAMMA) - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+enum Node {
+    Leaf {
+        size: usize,
+    },
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+impl Node {
+    fn build(rows: &[&Vec<f64>], depth: usize, max_depth: usize, rng: &mut ChaCha8Rng) -> Self {
+        if depth >= max_depth || rows.len() <= 1 {
+            return Node::Leaf { size: rows.len() };
+        }
+
+        // only features that can still separate rows are candidates
+        let nfeatures = rows[0].len();
+        let candidates: Vec<(usize, f64, f64)> = (0..nfeatures)
+            .filter_map(|f| {
+                let min = rows.iter().map(|r| r[f]).fold(f64::INFINITY, f64::min);
+                let max = rows.iter().map(|r| r[f]).fold(f64::NEG_INFINITY, f64::max);
+                if max > min { Some((f, min, max)) } else { None }
+            })
+            .collect();
+
+        let (feature, min, max) = match candidates.choose(rng) {
+            Some(candidate) => *candidate,
+            None => return Node::Leaf { size: rows.len() },
+        };
+        let threshold = rng.gen_range(min, max);
+        let (left, right): (Vec<&Vec<f64>>, Vec<&Vec<f64>>) =
+            rows.iter().copied().partition(|r| r[feature] < threshold);
+
+        Node::Split {
+            feature,
+            threshold,
+            left: Box::new(Node::build(&left, depth + 1, max_depth, rng)),
+            right: Box::new(Node::build(&right, depth + 1, max_depth, rng)),
+        }
+    }
+
+    /// Path length of row, crediting each split feature on the path
+    /// Splits closer to the root isolate more and get a larger share
+    ///
+    fn path_length(&self, row: &[f64], depth: usize, credits: &mut [f64]) -> f64 {
+        match self {
+            Node::Leaf { size } => depth as f64 + average_path_length(*size),
+            Node::Split { feature, threshold, left, right } => {
+                credits[*feature] += 1.0 / (depth + 1) as f64;
+                if row[*feature] < *threshold {
+                    left.path_length(row, depth + 1, credits)
+                } else {
+                    right.path_length(row, depth + 1, credits)
+                }
+            }
+        }
+    }
+}
+
+pub struct IsolationForest {
+    trees: Vec<Node>,
+    sample_size: usize,
+}
+
+impl IsolationForest {
+    /// Fit ntrees trees, each on a random subsample of rows
+    /// The same seed always produces the same forest
+    ///
+    pub fn fit(rows: &[Vec<f64>], ntrees: usize, sample_size: usize, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let sample_size = sample_size.min(rows.len()).max(1);
+        let max_depth = (sample_size as f64).log2().ceil() as usize;
+
+        let trees = (0..ntrees)
+            .map(|_| {
+                let sample: Vec<&Vec<f64>> = rows.choose_multiple(&mut rng, sample_size).collect();
+                Node::build(&sample, 0, max_depth, &mut rng)
+            })
+            .collect();
+
+        IsolationForest { trees, sample_size }
+    }
+
+    /// Anomaly score in (0, 1] (close to 1 is anomalous) and relative contribution of each feature
+    ///
+    pub fn score(&self, row: &[f64]) -> (f64, Vec<f64>) {
+        let mut credits = vec![0.0; row.len()];
+        let total: f64 = self
+            .trees
+            .iter()
+            .map(|tree| tree.path_length(row, 0, &mut credits))
+            .sum();
+        let mean_path = total / self.trees.len() as f64;
+        let score = 2f64.powf(-mean_path / average_path_length(self.sample_size).max(1.0));
+
+        let sum: f64 = credits.iter().sum();
+        if sum > 0.0 {
+            credits.iter_mut().for_each(|c| *c /= sum);
+        }
+        (score, credits)
+    }
+}
+
+/// Single scored row, with the columns that contributed most to isolate it
+///
+#[derive(Debug)]
+pub struct Anomaly {
+    pub row: usize,
+    pub score: f64,
+    pub contributions: Vec<(String, f64)>,
+}
+
+/// Score every row and return the top n most anomalous
+///
+pub fn top_anomalies(encoded: &Encoded, forest: &IsolationForest, n: usize, ncontrib: usize) -> Vec<Anomaly> {
+    let mut anomalies: Vec<Anomaly> = encoded
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(row, values)| {
+            let (score, credits) = forest.score(values);
+            let mut contributions: Vec<(String, f64)> = encoded
+                .columns
+                .iter()
+                .cloned()
+                .zip(credits.into_iter())
+                .collect();
+            contributions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+            contributions.truncate(ncontrib);
+            Anomaly { row, score, contributions }
+        })
+        .collect();
+
+    // ties are broken by row index to keep the output stable
+    anomalies.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal).then(a.row.cmp(&b.row)));
+    anomalies.truncate(n);
+    anomalies
+}
+
+/// Write anomalies as csv: row, score, contributing columns and the original values of the row
+///
+pub fn write_report<W: Write>(writer: W, dataframe: &NcodeDataFrame, anomalies: &[Anomaly]) -> csv::Result<()> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    let colnames = dataframe.dataframe.get_column_names();
+
+    let mut header: Vec<String> = vec!["row".into(), "score".into(), "contributions".into()];
+    header.extend(colnames.iter().map(|c| c.to_string()));
+    wtr.write_record(&header)?;
+
+    for anomaly in anomalies {
+        let contributions: Vec<String> = anomaly
+            .contributions
+            .iter()
+            .map(|(column, share)| format!("{}={:.3}", column, share))
+            .collect();
+        let mut record: Vec<String> = vec![
+            anomaly.row.to_string(),
+            format!("{:.4}", anomaly.score),
+            contributions.join(";"),
+        ];
+        for series in dataframe.dataframe.get_columns() {
+            record.push(format!("{}", series.get(anomaly.row)));
+        }
+        wtr.write_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isolated_point_has_highest_score() {
+        let mut rows: Vec<Vec<f64>> = (0..200)
+            .map(|i| vec![(i % 10) as f64 * 0.1, (i % 7) as f64 * 0.1])
+            .collect();
+        rows.push(vec![25.0, 0.3]);
+        let encoded = Encoded { columns: vec!["a".into(), "b".into()], rows };
+
+        let forest = IsolationForest::fit(&encoded.rows, 100, 64, 42);
+        let top = top_anomalies(&encoded, &forest, 1, 1);
+        assert_eq!(top[0].row, 200);
+        assert_eq!(top[0].contributions[0].0, "a");
+
+        // same seed, same scores
+        let again = IsolationForest::fit(&encoded.rows, 100, 64, 42);
+        assert_eq!(forest.score(&encoded.rows[3]).0, again.score(&encoded.rows[3]).0);
+    }
+}
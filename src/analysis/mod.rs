@@ -0,0 +1,2 @@
+pub mod anomalies;
+pub mod outliers;
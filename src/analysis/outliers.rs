@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::io::Write;
+
+
+/// Thresholds used to flag a value as outlier
+///
+#[derive(Debug, Clone, Copy)]
+pub struct OutlierConfig {
+    /// Absolute z-score above which a value is an outlier
+    pub zscore: f64,
+    /// Absolute modified z-score (based on median absolute deviation)
+    pub modified_zscore: f64,
+    /// Multiple of the interquartile range beyond Q1 and Q3
+    pub iqr_factor: f64,
+}
+
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self {
+            zscore: 3.0,
+            modified_zscore: 3.5,
+            iqr_factor: 1.5,
+        }
+    }
+}
+
+impl OutlierConfig {
+    /// Reason the thresholds cannot be used, if any: z-scores must be positive
+    /// (at zero every value is flagged) and the iqr factor not negative (the fences would cross)
+    ///
+    pub fn check(&self) -> Result<(), String> {
+        if self.zscore.is_nan() || self.zscore <= 0.0 {
+            return Err(format!("--zscore must be greater than 0, got {}", self.zscore));
+        }
+        if self.modified_zscore.is_nan() || self.modified_zscore <= 0.0 {
+            return Err(format!("--mad-zscore must be greater than 0, got {}", self.modified_zscore));
+        }
+        if self.iqr_factor.is_nan() || self.iqr_factor < 0.0 {
+            return Err(format!("--iqr-factor must be at least 0, got {}", self.iqr_factor));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OutlierMethod {
+    ZScore,
+    ModifiedZScore,
+    Iqr,
+}
+
+/// Number of outliers found by each method, and the IQR fences used
+///
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutlierCounts {
+    pub zscore: usize,
+    pub modified_zscore: usize,
+    pub iqr: usize,
+    pub lower_fence: f64,
+    pub upper_fence: f64,
+}
+
+/// Single value flagged by at least one method
+///
+#[derive(Debug, Clone)]
+pub struct Outlier {
+    pub row: usize,
+    pub value: f64,
+    pub methods: Vec<OutlierMethod>,
+}
+
+/// Quantile of sorted values with linear interpolation
+///
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    let weight = pos - lower as f64;
+    sorted[lower] * (1.0 - weight) + sorted[upper] * weight
+}
+
+/// Detect outliers among values, where the position in the slice is the row index
+/// Nulls are ignored
+///
+pub fn detect(values: &[Option<f64>], config: &OutlierConfig) -> (OutlierCounts, Vec<Outlier>) {
+    let mut sorted: Vec<f64> = values.iter().filter_map(|v| *v).filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return (OutlierCounts::default(), vec![]);
+    }
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().sum::<f64>() / n;
+    let std = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+
+    let median = quantile(&sorted, 0.5);
+    let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+    deviations.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let mad = quantile(&deviations, 0.5);
+
+    let q1 = quantile(&sorted, 0.25);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let lower_fence = q1 - config.iqr_factor * iqr;
+    let upper_fence = q3 + config.iqr_factor * iqr;
+
+    let mut counts = OutlierCounts {
+        lower_fence,
+        upper_fence,
+        ..Default::default()
+    };
+    let mut outliers: Vec<Outlier> = vec![];
+
+    for (row, value) in values.iter().enumerate() {
+        let value = match value {
+            Some(v) if v.is_finite() => *v,
+            _ => continue,
+        };
+        let mut methods: Vec<OutlierMethod> = vec![];
+
+        // constant columns have no outliers by z-score or MAD
+        if std > 0.0 && ((value - mean) / std).abs() > config.zscore {
+            counts.zscore += 1;
+            methods.push(OutlierMethod::ZScore);
+        }
+        if mad > 0.0 && (0.6745 * (value - median) / mad).abs() > config.modified_zscore {
+            counts.modified_zscore += 1;
+            methods.push(OutlierMethod::ModifiedZScore);
+        }
+        if value < lower_fence || value > upper_fence {
+            counts.iqr += 1;
+            methods.push(OutlierMethod::Iqr);
+        }
+
+        if !methods.is_empty() {
+            outliers.push(Outlier { row, value, methods });
+        }
+    }
+
+    (counts, outliers)
+}
+
+/// Write outliers of each column as csv (column, row, value, methods)
+///
+pub fn write_report<W: Write>(writer: W, outliers: &[(String, Outlier)]) -> csv::Result<()> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(&["column", "row", "value", "methods"])?;
+
+    for (column, outlier) in outliers {
+        let methods: Vec<String> = outlier.methods.iter().map(|m| format!("{:?}", m)).collect();
+        wtr.write_record(&[
+            column.clone(),
+            outlier.row.to_string(),
+            outlier.value.to_string(),
+            methods.join(";"),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_thresholds_flagging_every_value() {
+        assert!(OutlierConfig::default().check().is_ok());
+        let config = |zscore, modified_zscore, iqr_factor| OutlierConfig { zscore, modified_zscore, iqr_factor };
+        assert!(config(3.0, 3.5, 0.0).check().is_ok());
+        assert!(config(0.0, 3.5, 1.5).check().unwrap_err().starts_with("--zscore"));
+        assert!(config(3.0, -1.0, 1.5).check().unwrap_err().starts_with("--mad-zscore"));
+        assert!(config(3.0, f64::NAN, 1.5).check().is_err());
+        assert!(config(3.0, 3.5, -0.5).check().unwrap_err().starts_with("--iqr-factor"));
+    }
+
+    #[test]
+    fn unit_mistake_is_flagged_by_every_method() {
+        let mut values: Vec<Option<f64>> = (0..100).map(|i| Some(10.0 + (i % 7) as f64)).collect();
+        values.push(None);
+        values.push(Some(1e9));
+
+        let (counts, outliers) = detect(&values, &OutlierConfig::default());
+        assert_eq!(counts.zscore, 1);
+        assert_eq!(counts.modified_zscore, 1);
+        assert_eq!(counts.iqr, 1);
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].row, 101);
+        assert_eq!(outliers[0].methods.len(), 3);
+    }
+}
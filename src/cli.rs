@@ -14,7 +14,7 @@ use clap::Clap;
 // cargo run -- profile -i filename.csv
 
 // xtract profile --input=mydata.csv --output=meta.txt --publish=true
-// xtract profile --input=s3://mydata.csv --output=meta.txt --publish=true
+// xtract profile --input=s3://bucket/mydata.csv --output=meta.txt --publish=true
 // xtract profile -i ./data/filename.csv --name custom_name.csv --publish
 
 // Get all alerts of data_id = 0x1234
@@ -51,6 +51,31 @@ pub struct Alerts {
     pub delete: bool,
 }
 
+/// Which objects under a prefix (s3://bucket/drops/) to read
+#[derive(Clap, Clone)]
+pub struct Listing {
+    /// Only keys below the prefix matching this glob, e.g. '2020-12-*/*.csv'
+    #[clap(long)]
+    pub glob: Option<String>,
+
+    /// Only keys ending with this suffix, e.g. .csv.gz
+    #[clap(long)]
+    pub suffix: Option<String>,
+
+    /// Only objects modified at or after this date (2020-12-01) or time (2020-12-01T06:00:00)
+    #[clap(long)]
+    pub modified_since: Option<String>,
+}
+
+#[derive(Clap, Clone)]
+pub struct Ls {
+    /// Prefix to list, e.g. s3://bucket/drops/
+    pub uri: String,
+
+    #[clap(flatten)]
+    pub listing: Listing,
+}
+
 #[derive(Clap, Clone)]
 pub struct Profile {
     #[clap(short, long)]
@@ -59,6 +84,134 @@ pub struct Profile {
     // #[clap(long, conflicts_with="id", takes_value=false)]
     #[clap(long, takes_value = false)]
     pub publish: bool,
+
+    /// Read csv input from an arrow copy (input.arrow), written on first use and when the csv changes
+    #[clap(long, takes_value = false)]
+    pub cache: bool,
+
+    /// Profile only this column (can be repeated); parquet files only decode these columns
+    #[clap(long = "column")]
+    pub columns: Vec<String>,
+
+    /// Extra token to read as a missing value (can be repeated)
+    #[clap(long = "null-token")]
+    pub null_tokens: Vec<String>,
+
+    /// Token to read as a missing value in one column only, as column=token (can be repeated)
+    #[clap(long = "column-null-token")]
+    pub column_null_tokens: Vec<String>,
+
+    /// Write rows of numeric columns flagged as outliers to this csv file
+    #[clap(long)]
+    pub outliers: Option<String>,
+
+    /// Absolute z-score above which a value is an outlier
+    #[clap(long, default_value = "3.0")]
+    pub zscore: f64,
+
+    /// Absolute modified z-score (median absolute deviation) above which a value is an outlier
+    #[clap(long = "mad-zscore", default_value = "3.5")]
+    pub mad_zscore: f64,
+
+    /// Multiple of the interquartile range beyond which a value is an outlier
+    #[clap(long = "iqr-factor", default_value = "1.5")]
+    pub iqr_factor: f64,
+
+    /// Write the profile to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<String>,
+
+    /// Format of the profile: json, json-pretty, yaml, markdown, csv-summary or html
+    /// (default from the extension of --output, json-pretty otherwise)
+    #[clap(short, long)]
+    pub format: Option<String>,
+
+    /// Print a summary table with histograms even when stdout is not a terminal
+    #[clap(long, takes_value = false)]
+    pub summary: bool,
+
+    /// Profile all objects under a prefix (or files of an archive) as one dataset instead of one by one
+    #[clap(long, takes_value = false)]
+    pub combine: bool,
+
+    #[clap(flatten)]
+    pub listing: Listing,
+}
+
+#[derive(Clap, Clone)]
+pub struct Anomalies {
+    #[clap(short, long)]
+    pub input: String,
+
+    /// Number of most anomalous rows to report
+    #[clap(long, default_value = "20")]
+    pub top: usize,
+
+    /// Seed of the random number generator, the same seed gives the same scores
+    #[clap(long, default_value = "42")]
+    pub seed: u64,
+
+    /// Number of trees in the isolation forest
+    #[clap(long, default_value = "100")]
+    pub trees: usize,
+
+    /// Number of rows sampled to build each tree
+    #[clap(long = "sample-size", default_value = "256")]
+    pub sample_size: usize,
+
+    /// Write anomalous rows to this csv file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Clap, Clone)]
+pub struct Validate {
+    #[clap(short, long)]
+    pub input: String,
+
+    /// Expectations file (toml)
+    #[clap(short, long)]
+    pub expectations: String,
+
+    /// Write rows violating an expectation to this csv file
+    #[clap(long)]
+    pub failures: Option<String>,
+}
+
+#[derive(Clap, Clone)]
+pub struct Expectations {
+    /// Data to profile, or a profile previously saved as json
+    #[clap(short, long)]
+    pub input: String,
+
+    /// Expectations file to write (toml), stdout if missing
+    #[clap(short, long)]
+    pub output: Option<String>,
+
+    /// Relative margin added to observed ranges and null ratios
+    #[clap(long, default_value = "0.1")]
+    pub tolerance: f64,
+}
+
+#[derive(Clap, Clone)]
+pub struct Transform {
+    #[clap(short, long)]
+    pub input: String,
+
+    /// Where to write transformed data: arrow IPC file (.arrow, .feather), stream (.arrows) or .csv
+    #[clap(short, long)]
+    pub output: String,
+
+    /// Profile saved as json driving the transformation, computed from input if missing
+    #[clap(long)]
+    pub profile: Option<String>,
+}
+
+#[derive(Clap, Clone)]
+pub struct Explore {
+    /// Data to profile and browse, or a profile previously saved as json
+    #[clap(short, long)]
+    pub input: String,
 }
 
 #[derive(Clap)]
@@ -76,6 +229,18 @@ pub enum SubCommand {
     // Publish,
     /// Profile of data passed as argument
     Profile(Profile),
+    /// Score rows of data passed as argument by how anomalous they are
+    Anomalies(Anomalies),
+    /// Check data passed as argument against an expectations file
+    Validate(Validate),
+    /// Generate starter expectations from the profile of data passed as argument
+    Expectations(Expectations),
+    /// Transform data passed as argument using its profile
+    Transform(Transform),
+    /// Browse the profile of data passed as argument in a terminal UI
+    Explore(Explore),
+    /// List objects under a prefix
+    Ls(Ls),
 }
 
 #[derive(Clap)]
@@ -90,6 +255,27 @@ pub struct Args {
     #[clap(long)]
     pub input: Option<String>,
 
-    #[clap(short, long)]
-    _verbose: Option<i32>,
+    /// More diagnostics on stderr (-v debug, -vv trace)
+    #[clap(short, long, global = true, parse(from_occurrences))]
+    pub verbose: u64,
+
+    /// Only errors on stderr, no progress bars
+    #[clap(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Format of diagnostics: text or json (one object per line)
+    #[clap(long, global = true, default_value = "text")]
+    pub log_format: String,
+
+    /// Named storage profile for s3:// inputs (default: the profile listing the bucket, or [storage])
+    #[clap(long, global = true)]
+    pub storage_profile: Option<String>,
+
+    /// Read only the first bytes of csv and json lines inputs (ranged requests for s3 and http)
+    #[clap(long, global = true)]
+    pub sample_bytes: Option<u64>,
+
+    /// Tag objects written to buckets with the uri and fingerprint (etag) of the input
+    #[clap(long, global = true, takes_value = false)]
+    pub tag_source: bool,
 }
@@ -1,7 +1,10 @@
-use super::cli::{Args, SubCommand};
-use anyhow::Result;
+use super::cli::{Args, Listing, SubCommand};
+use super::explorer;
+use anyhow::{anyhow, Context, Result};
+use log::{debug, error, info, warn};
+use arrow::datatypes::Schema;
 use arrow::record_batch::RecordBatch;
-use once_cell::sync::Lazy;
+use once_cell::unsync::OnceCell;
 use serde_json::{json, Value};
 use serde::{Serialize, Deserialize};
 // use serde::de::{
@@ -16,17 +19,26 @@ use std::env::var;
 use std::path::PathBuf;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::Cursor;
-use std::sync::Arc;
+use std::io::{BufRead, BufReader, Cursor, Seek};
 use std::ops::Not;
-use std::process;
-use tokio::runtime::Runtime;
-use xtract::configuration::{get_configuration_from_file, get_content_from_file};
-use xtract::loaders::s3_connector::Storage;
-// TODO remove and use only polars DataFrame
-use xtract::loaders::dataframe::NcodeDataFrame;
-use xtract::loaders::frame::DataFrame;
-// use crate::transformers::simple;
+use std::sync::Arc;
+use xtract::configuration::{get_configuration_from_file, get_content_from_file, Config};
+use xtract::analysis::anomalies::{self, IsolationForest};
+use xtract::analysis::outliers::{self, OutlierConfig};
+use xtract::expectations::{generate, validate, ExpectationSuite};
+use xtract::loaders::dataframe::{DataFrameMeta, NcodeDataFrame};
+use xtract::loaders::compression::{self, Compression};
+use xtract::loaders::format::InputFormat;
+use xtract::loaders::{csv_format, ipc_format};
+use xtract::loaders::json_format::JsonReader;
+use xtract::loaders::nulls::NullTokens;
+use xtract::loaders::parquet_format::{ParquetReader, RangeChunkReader};
+use xtract::loaders::sink;
+use xtract::loaders::source::{DataSource, ListFilter, Registry, SourceMeta};
+use parquet::file::reader::ChunkReader;
+use parquet::util::cursor::SliceableCursor;
+use xtract::reports::{self, terminal, OutputFormat};
+use xtract::transformers::simple::Transformer;
 use xtract::loaders::csv_format::CsvReader as csvr;
 use polars::prelude::*;
 // use arrow::datatypes::DataType;
@@ -34,7 +46,6 @@ use polars::prelude::*;
 // use arrow::util::print_batches;
 
 // TODO move to config.rs or something
-static RT: Lazy<Runtime> = Lazy::new(|| Runtime::new().unwrap());
 const CONFIG_DIR: &str = ".ncode";
 const CONFIG_FILENAME: &str = "configuration.toml";
 const CONFIG_SAMPLE_FILENAME: &str = "configuration-sample.toml";
@@ -50,15 +61,67 @@ password = \"password\"
 
 [settings]
 
+[nulls]
+tokens = [\"\", \"NA\", \"N/A\", \"null\", \"NULL\", \"-\", \"?\", \"0000-00-00\"]
+
+[nulls.columns]
+# amount = [\"0\"]
+
+[http.hosts]
+# \"data.example.org\" = { token = \"token\" }
+# \"files.internal\" = { username = \"username\", password = \"password\" }
+
 [storage]
-url = \"http://my-s3-storage\"
-access_key = \"access_key\"
-secret_access_key = \"secret_access_key\"
+# left out, settings come from the environment (AWS_REGION, ...) and the standard AWS credential chain
+# url = \"http://my-s3-storage\"
+# region = \"us-east-1\"
+# access_key = \"access_key\"
+# secret_access_key = \"secret_access_key\"
+
+# [storage.profiles.landing]
+# region = \"eu-west-1\"
+# buckets = [\"landing-zone\"]
 ";
 
 
+/// Failures of commands other than reading data or configuration, told apart by main for exit codes
+#[derive(Debug)]
+pub enum ClientError {
+    /// Arguments missing or not usable together
+    Usage(String),
+    /// Remote service unreachable or answering with an error
+    Remote(String),
+    /// Data failing some of its expectations, with the number of failed checks
+    ExpectationsFailed(usize),
+    /// Command not available yet
+    Unsupported(String),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Usage(message) => write!(f, "{}", message),
+            ClientError::Remote(message) => write!(f, "{}", message),
+            ClientError::ExpectationsFailed(failed) => write!(f, "{} expectation(s) failed", failed),
+            ClientError::Unsupported(command) => write!(f, "{} is not supported yet", command),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
 pub struct Frontend {
     args: Args,
+    /// Where inputs are read from, by uri scheme, with the credentials from config
+    sources: OnceCell<Registry>,
+}
+
+/// Input data on disk, or decompressed in memory
+enum Content {
+    File(File),
+    Bytes(Vec<u8>),
+    /// Not seekable, e.g. stdin
+    Stream(Box<dyn BufRead>),
 }
 
 #[cfg(feature = "async_await")]
@@ -110,12 +173,16 @@ impl AlertResponse {
 
 impl Frontend {
     pub fn new(args: Args) -> Self {
-        Frontend { args }
+        Frontend {
+            args,
+            sources: OnceCell::new(),
+        }
     }
 
     pub fn run(&self) -> Result<()> {
         // check if exists
-        let mut config_path = PathBuf::from(var("HOME").unwrap());
+        let home = var("HOME").map_err(|_| ClientError::Usage(String::from("HOME is not set, cannot find ~/.ncode")))?;
+        let mut config_path = PathBuf::from(home);
         config_path.push(CONFIG_DIR);
 
         let mut config_file_path = config_path.clone();
@@ -124,35 +191,32 @@ impl Frontend {
         let mut token_file_path = config_path.clone();
         token_file_path.push(".token");
 
-        let config_file_path = config_file_path
-            .into_os_string()
-            .into_string()
-            .unwrap();
+        let config_file_path = config_file_path.to_string_lossy().into_owned();
         let config_path_exist = config_path.exists();
 
         if !config_path_exist {
-            println!("Creating ncode home folder for the first time...");
-            let config_path = config_path
-                .clone()
-                .into_os_string()
-                .into_string()
-                .unwrap();
-
-            std::fs::create_dir_all(config_path.clone()).unwrap();
-            let mut file = File::create(format!("{}/{}", config_path, CONFIG_SAMPLE_FILENAME))
-                .unwrap();
-            file.write_all(CONFIG_SAMPLE_CONTENT.as_bytes()).unwrap();
-            println!("done.");
+            info!("Creating ncode home folder for the first time...");
+            std::fs::create_dir_all(&config_path)?;
+            let mut file = File::create(config_path.join(CONFIG_SAMPLE_FILENAME))?;
+            file.write_all(CONFIG_SAMPLE_CONTENT.as_bytes())?;
+            info!("done.");
         }
 
         // get configuration
         let config = get_configuration_from_file(&config_file_path[..])?;
+        self.sources
+            .set(
+                Registry::default()
+                    .with_http(config.http.hosts.clone())
+                    .with_storage(config.storage.clone(), self.args.storage_profile.clone()),
+            )
+            .map_err(|_| anyhow!("Data sources are already configured"))?;
 
         // TODO different type dispatcher
 
         // get API url
         let url = format!("http://{}:{}", config.api.server, config.api.port);
-        let tokenfile = token_file_path.into_os_string().into_string().unwrap();
+        let tokenfile = token_file_path.to_string_lossy().into_owned();
 
         match &self.args.subcmd {
             SubCommand::Login => {
@@ -195,8 +259,7 @@ impl Frontend {
                 }
 
                 if res.contains_key("status").not() {
-                    println!("Error establishing connection.\nServer can be down.");
-                    process::exit(1);
+                    return Err(ClientError::Remote(String::from("Error establishing connection. Server can be down.")).into());
                 }
 
                 match &res["status"][..] {
@@ -210,25 +273,29 @@ impl Frontend {
                                 println!("Found {} data asset(s)", da.len());
 
                                 for (i, asset) in da.iter().enumerate() {
+                                    let field = |key: &str| asset.get(key).map(String::as_str).unwrap_or_default();
                                     println!("\n********** DATA ASSET {}", i);
-                                    println!("id: {}", asset["id"]);
-                                    println!("type: {}", asset["type"]);
-                                    println!("filename: {}", asset["filename"]);
-                                    println!("submitted_on: {}", asset["_submitted_on"]);
-                                    println!("datastore: {}", asset["datastore"]);
+                                    println!("id: {}", field("id"));
+                                    println!("type: {}", field("type"));
+                                    println!("filename: {}", field("filename"));
+                                    println!("submitted_on: {}", field("_submitted_on"));
+                                    println!("datastore: {}", field("datastore"));
                                     if delete_data {
-                                        let endpoint = format!("{}/data/{}", url, asset["id"]);
-                                        let _res = self.del_helper(endpoint, tokenfile.clone());
+                                        let endpoint = format!("{}/data/{}", url, field("id"));
+                                        success(&self.del_helper(endpoint, tokenfile.clone())?, "Cannot delete data asset")?;
                                     }
                                 }
                             },
                             Err(e) => {
-                                println!("There seems to be no data assets yet. [Err: {:?}]", e);
-                                process::exit(-1);
+                                return Err(ClientError::Remote(format!(
+                                    "There seems to be no data assets yet. [Err: {:?}]",
+                                    e
+                                ))
+                                .into());
                             }
                         }
                     },
-                    _ => println!("Status not OK"),
+                    _ => success(&res, "Cannot retrieve data asset(s)")?,
                 }
 
                 Ok(())
@@ -257,8 +324,7 @@ impl Frontend {
 
                 // exit if no flag is passed
                 if !(get_all_alerts || get_single_alert) {
-                    println!("Must pass at least one flag --data or --id ");
-                    process::exit(-1);
+                    return Err(ClientError::Usage(String::from("Must pass at least one flag --data or --id")).into());
                 }
 
                 let delete_alert = t.delete;
@@ -268,7 +334,7 @@ impl Frontend {
                     let endpoint = format!("{}/data/{}/alerts", url, data_id);
                     res = self.get_helper(endpoint, tokenfile.clone())?;
                     if delete_alert {
-                        println!("TODO create endpoint DEL /alerts/:id for each :id");
+                        warn!("TODO create endpoint DEL /alerts/:id for each :id");
                     }
                 }
 
@@ -276,14 +342,15 @@ impl Frontend {
                     let endpoint = format!("{}/alerts/{}", url, alert_id);
                     res = self.get_helper(endpoint, tokenfile.clone())?;
                     if delete_alert {
-                        println!("TODO create endpoint DEL /alerts/:id");
+                        warn!("TODO create endpoint DEL /alerts/:id");
                     }
                 }
 
-                match &res["status"][..] {
-                    "success" => {
+                match res.get("status").map(String::as_str) {
+                    Some("success") => {
                         // let alerts = serde_json::from_str::<Vec<HashMap<String, String>>>(res["message"].as_str()).unwrap();
-                        let alerts = serde_json::from_str::<Vec<AlertResponse>>(res["message"].as_str()).unwrap();
+                        let alerts = serde_json::from_str::<Vec<AlertResponse>>(res.get("message").map(String::as_str).unwrap_or_default())
+                            .map_err(|e| ClientError::Remote(format!("Cannot read alerts: {}", e)))?;
                         // println!("DBG DBG {:?} len={}", alerts, alerts.len());
 
                         if alerts.len() ==  0 {
@@ -306,139 +373,258 @@ impl Frontend {
 
                                 if delete_alert {
                                     let endpoint = format!("{}/alerts/{}", url, alert.id);
-                                    let res = self.del_helper(endpoint, tokenfile.clone());
+                                    success(&self.del_helper(endpoint, tokenfile.clone())?, "Cannot delete alert")?;
                                 }
 
                             }
 
                         }
                     },
-                    _ => println!("Cannot retrieve alert(s). Status not OK"),
+                    _ => success(&res, "Cannot retrieve alert(s)")?,
                 }
 
                 Ok(())
             },
 
-            SubCommand::Set => unimplemented!(),
+            SubCommand::Set => Err(ClientError::Unsupported(String::from("set")).into()),
 
             // SubCommand::Publish => {
             //     unimplemented!()
             // },
 
 
+            SubCommand::Anomalies(t) => {
+                // a forest needs trees, and a tree needs two rows to split
+                if t.trees == 0 {
+                    return Err(ClientError::Usage(String::from("--trees must be at least 1")).into());
+                }
+                if t.sample_size < 2 {
+                    return Err(ClientError::Usage(String::from("--sample-size must be at least 2")).into());
+                }
+                let null_tokens = self.null_tokens_helper(&config, &[], &[])?;
+                let dataframe = self.dataset_helper(&t.input, &[])?.with_null_tokens(&null_tokens)?;
+
+                // categorical flags and numeric stats of the profile drive the encoding
+                let profile = dataframe.profile()?;
+                let encoded = anomalies::encode(&dataframe, &profile)?;
+                debug!("Scoring {} rows over columns {:?}", encoded.rows.len(), encoded.columns);
+
+                let forest = IsolationForest::fit(&encoded.rows, t.trees, t.sample_size, t.seed);
+                let top = anomalies::top_anomalies(&encoded, &forest, t.top, 3);
+
+                match &t.output {
+                    Some(path) => {
+                        let mut content: Vec<u8> = vec![];
+                        anomalies::write_report(&mut content, &dataframe, &top)?;
+                        self.put_helper(path, &content, "text/csv", dataframe.source())?;
+                        info!("{} anomalous row(s) written to {}", top.len(), path);
+                    }
+                    None => anomalies::write_report(std::io::stdout(), &dataframe, &top)?,
+                }
+                Ok(())
+            }
+
+            SubCommand::Validate(t) => {
+                let suite = ExpectationSuite::from_file(&t.expectations)?;
+                let null_tokens = self.null_tokens_helper(&config, &[], &[])?;
+                let dataframe = self.dataset_helper(&t.input, &[])?.with_null_tokens(&null_tokens)?;
+
+                let report = validate::validate(&dataframe, &suite)?;
+                println!("{}", report);
+
+                if let Some(path) = &t.failures {
+                    let mut content: Vec<u8> = vec![];
+                    report.write_failing_rows(&mut content, &dataframe)?;
+                    self.put_helper(path, &content, "text/csv", dataframe.source())?;
+                    info!("{} violating row(s) written to {}", report.failing_rows.len(), path);
+                }
+
+                if !report.passed() {
+                    return Err(ClientError::ExpectationsFailed(report.num_failed()).into());
+                }
+                Ok(())
+            }
+
+            SubCommand::Expectations(t) => {
+                let profile: DataFrameMeta = if let Some(profile) = Self::saved_profile_helper(&t.input)? {
+                    profile
+                } else {
+                    let null_tokens = self.null_tokens_helper(&config, &[], &[])?;
+                    self.dataset_helper(&t.input, &[])?.with_null_tokens(&null_tokens)?.profile()?
+                };
+
+                let suite = generate::generate_suite(&profile, t.tolerance);
+                let content = suite.to_toml()?;
+                match &t.output {
+                    Some(path) => {
+                        self.put_helper(path, content.as_bytes(), "application/toml", profile.source())?;
+                        info!("{} column expectation(s) written to {}", suite.columns.len(), path);
+                    }
+                    None => println!("{}", content),
+                }
+                Ok(())
+            }
+
+            SubCommand::Transform(t) => {
+                let null_tokens = self.null_tokens_helper(&config, &[], &[])?;
+                let dataframe = self.dataset_helper(&t.input, &[])?.with_null_tokens(&null_tokens)?;
+                let profile = match &t.profile {
+                    Some(path) => Self::saved_profile_helper(path)?
+                        .ok_or_else(|| anyhow!("{} is not a saved profile", path))?,
+                    None => dataframe.profile()?,
+                };
+
+                let source = dataframe.source().cloned();
+                let transformed = Transformer::new((*dataframe.dataframe).clone(), profile).transform()?;
+                self.write_data_helper(&NcodeDataFrame::new(transformed), &t.output, source.as_ref())
+            }
+
+            SubCommand::Explore(t) => {
+                // a saved profile has no data to preview
+                if let Some(profile) = Self::saved_profile_helper(&t.input)? {
+                    explorer::run(profile, None)
+                } else {
+                    let null_tokens = self.null_tokens_helper(&config, &[], &[])?;
+                    let ndf = self.dataset_helper(&t.input, &[])?.with_null_tokens(&null_tokens)?;
+                    let mut profile = ndf.profile()?;
+                    profile.set_datasource(t.input.clone());
+                    explorer::run(profile, Some(ndf))
+                }
+            }
+
+            SubCommand::Ls(t) => {
+                let filter = self.list_filter_helper(&t.listing)?;
+                for object in self.sources()?.list(&t.uri, &filter)? {
+                    println!(
+                        "{}\t{}\t{}",
+                        object.last_modified.unwrap_or_default(),
+                        object.size.map(|s| s.to_string()).unwrap_or_default(),
+                        object.uri
+                    );
+                }
+                Ok(())
+            }
+
             SubCommand::Profile(t) => {
 
                 let input_to_fetch = &t.input;
                 let publish_to_api = t.publish;
-                println!("Publish after profile: {:?}", publish_to_api);
-
-                let input_location: String = input_to_fetch.chars().skip(0).take(5).collect();
-
-                match input_location.as_ref() {
-                    // try s3 bucket
-                    "s3://" => {
-                        // TODO integrate DataFrame
-                        let filename: String = input_to_fetch.chars().skip(5).collect();
-                        println!("filename input_to_fetch: {}", filename);
-                        let storage = Storage::new();
-                        // TODO get this from input_to_fetch
-                        // let filename = String::from("synthetic_demo_data.csv");
-                        let df = self.csv_reader_helper(storage, filename);
-                        let _profile = df.profile();
-                        // println!("Dataset profile: {}", profile);
+                debug!("Publish after profile: {:?}", publish_to_api);
+
+                let null_tokens = self.null_tokens_helper(&config, &t.null_tokens, &t.column_null_tokens)?;
+
+                let outlier_config = OutlierConfig {
+                    zscore: t.zscore,
+                    modified_zscore: t.mad_zscore,
+                    iqr_factor: t.iqr_factor,
+                };
+                outlier_config.check().map_err(ClientError::Usage)?;
+                // a prefix or a zip archive with several files gives one profile per file,
+                // or a single one with --combine
+                let partitions = self.sources_helper(input_to_fetch, &self.list_filter_helper(&t.listing)?)?;
+                let sources = if t.combine { vec![input_to_fetch.clone()] } else { partitions.clone() };
+                // one output per input, named after its path under the prefix or in the archive
+                let outputs = Self::member_outputs_helper(t.output.as_deref(), input_to_fetch, &sources)?;
+                let reports = Self::member_outputs_helper(t.outliers.as_deref(), input_to_fetch, &sources)?;
+                for (source, (output, report)) in sources.iter().zip(outputs.into_iter().zip(reports)) {
+                    let dataframe = if t.combine {
+                        self.partitioned_dataset_helper(source, &partitions, &t.columns)?
+                    } else if t.cache {
+                        self.cached_dataset_helper(source, &t.columns)?
+                    } else {
+                        self.dataset_helper(source, &t.columns)?
+                    };
+                    let dataframe = dataframe
+                        .with_null_tokens(&null_tokens)?
+                        .with_outlier_config(outlier_config);
+
+                    if let Some(report) = report {
+                        let found = dataframe.outliers()?;
+                        let mut content: Vec<u8> = vec![];
+                        outliers::write_report(&mut content, &found)?;
+                        self.put_helper(&report, &content, "text/csv", dataframe.source())?;
+                        info!("{} outlier(s) written to {}", found.len(), report);
                     }
 
-                    // try local file
-                    _ => {
-                        // input_to_fetch is a local file
-                        let file = File::open(input_to_fetch.clone()).expect("could not read file");
-
-                        let df = CsvReader::new(file)
-                            .infer_schema(None)
-                            .has_header(true)
-                            .finish()
-                            .unwrap();
-
-                        // dbg!(&df);
-                        let dataframe = NcodeDataFrame {
-                            dataframe: Arc::new(df),
-                        };
-
-                        // TODO Result(profile)
-                        let mut profile = dataframe.profile();
-
-                        // add filename to profile
-                        profile.set_datasource(input_to_fetch.clone());
-                        // Convert to string and print
-                        // let profile_str = serde_json::to_string_pretty(&profile).unwrap();
-                        let profile_str = serde_json::to_value(&profile).unwrap();
-                        // println!("Profile: {}", profile_str);
-
-                        if publish_to_api {
-                            // post profile to new url
-                            let post_data_endpoint = format!("{}/data/", url);
-                            let data_body = json!({"type": "local", "filename": format!("{}", input_to_fetch.clone()) });
-                            // println!("DBG body: {:?}", data_body);
-                            // println!("DBG body.to_string(): {:?}", data_body.to_string());
-
-                            let res: HashMap<String, String> = self
-                                .post_helper(post_data_endpoint, tokenfile.clone(), data_body)
-                                .unwrap();
-
-                            println!("DBG POST req res: {:?}", &res);
-                            // println!("data_id: {:?}", res.get("data_id"));
-
-                            // get data_id from response
-                            match res.get("data_id") {
-                                Some(did) => {
-                                    // println!("DBG in match did: {}", did);
-
-                                    let post_profile_endpoint = format!("{}/data/{}/profile", url, did);
-                                    let profile_res = self
-                                         .post_helper(post_profile_endpoint, tokenfile.clone(), json!(profile_str))
-                                         .unwrap();
-
-                                    // println!("DBG profile_res {:?}", &profile_res);
-
-                                    let status = profile_res.get("status"); // .unwrap();
-
-                                    match status {
-                                        Some(s) => {
-                                            println!("status: {}", s);
-                                            println!("message: {}", profile_res.get("message").unwrap());
-                                            },
-
-                                        _ => {
-                                            println!("status: None");
-                                            // println!("message: {}", profile_res.get("message").unwrap());
+                    let mut profile = dataframe.profile()?;
+
+                    // add filename to profile
+                    profile.set_datasource(source.clone());
+                    // summary for humans, the full profile when asked for or piped
+                    let interactive = atty::is(atty::Stream::Stdout);
+                    let show_summary = interactive || t.summary;
+                    if show_summary {
+                        println!("{}", terminal::render(&profile, terminal::terminal_width(), interactive));
+                    }
+                    if output.is_some() || t.format.is_some() || !show_summary {
+                        self.output_helper(&profile, output.as_deref(), t.format.as_deref())?;
+                    }
+                    let profile_str = serde_json::to_value(&profile)?;
+
+                    if publish_to_api {
+                        // post profile to new url
+                        let post_data_endpoint = format!("{}/data/", url);
+                        let data_body = json!({"type": self.sources()?.open(source)?.kind(), "filename": source.clone() });
+                        // println!("DBG body: {:?}", data_body);
+                        // println!("DBG body.to_string(): {:?}", data_body.to_string());
+
+                        let res: HashMap<String, String> = self
+                            .post_helper(post_data_endpoint, tokenfile.clone(), data_body)?;
+
+                        debug!("POST data response: {:?}", &res);
+                        // println!("data_id: {:?}", res.get("data_id"));
+
+                        // get data_id from response
+                        match res.get("data_id") {
+                            Some(did) => {
+                                // println!("DBG in match did: {}", did);
+
+                                let post_profile_endpoint = format!("{}/data/{}/profile", url, did);
+                                let profile_res = self
+                                     .post_helper(post_profile_endpoint, tokenfile.clone(), json!(profile_str))?;
+
+                                // println!("DBG profile_res {:?}", &profile_res);
+
+                                let status = profile_res.get("status"); // .unwrap();
+
+                                match status {
+                                    Some(s) => {
+                                        info!("status: {}", s);
+                                        info!("message: {}", profile_res.get("message").map(String::as_str).unwrap_or_default());
                                         },
-                                    }
 
-                                    // match profile_res.get("message") {
-                                    //     Some(msg) => {
-                                    //         println!("{}\n", msg);
-                                    //     },
-                                    //     None => {
-                                    //         println!("Something went wrong ");
-                                    //         process::exit(1);
-                                    //     }
-                                    // }
-
-                                },
-
-                                None => {
-                                    println!("No data_id returned from server. Contact an administrator at hello@ncode.ai");
-                                    process::exit(1);
+                                    _ => {
+                                        warn!("status: None");
+                                        // info!("message: {}", profile_res.get("message").unwrap());
+                                    },
                                 }
-                            }
 
-                            // let post_profile_endpoint = format!("{}/data/{}/profile", url, data_id);
-                            // println!("DBG ready to hit endpoint {:?} ", post_profile_endpoint);
-                            // let res = self
-                            //     .post_helper(post_profile_endpoint, tokenfile, json!(profile_str))
-                            //     .unwrap();
-                            // println!("DBG POST req res: {:?}", res);
+                                // match profile_res.get("message") {
+                                //     Some(msg) => {
+                                //         println!("{}\n", msg);
+                                //     },
+                                //     None => {
+                                //         println!("Something went wrong ");
+                                //         process::exit(1);
+                                //     }
+                                // }
+
+                            },
+
+                            None => {
+                                return Err(ClientError::Remote(String::from(
+                                    "No data_id returned from server. Contact an administrator at hello@ncode.ai",
+                                ))
+                                .into());
+                            }
                         }
+
+                        // let post_profile_endpoint = format!("{}/data/{}/profile", url, data_id);
+                        // println!("DBG ready to hit endpoint {:?} ", post_profile_endpoint);
+                        // let res = self
+                        //     .post_helper(post_profile_endpoint, tokenfile, json!(profile_str))
+                        //     .unwrap();
+                        // println!("DBG POST req res: {:?}", res);
                     }
                 }
                 Ok(())
@@ -446,52 +632,494 @@ impl Frontend {
         }
     }
 
-    // TODO rename this to csv_reader_from_s3
-    /// Get filename from S3 bucket (storage) before profiling
+    /// Registry of data sources, set with credentials once the config is read
     ///
-    fn csv_reader_helper(&self, storage: Storage, filename: String) -> DataFrame {
-        let fut = async { storage.get_object(filename).await };
-        let (data, data_type) = RT.handle().block_on(fut);
-        println!("content type: {}", data_type);
-
-        let data_str = String::from_utf8(data).unwrap();
-        let file = Cursor::new(&data_str[..]);
-        // from here on it's like a regular file descriptor
-        let mut reader = csvr::new(file)
-            .infer_schema(100)
-            .has_header(true)
-            .with_batch_size(128)
-            .finish();
-
-        let mut batches: Vec<RecordBatch> = vec![];
-        let mut has_next = true;
-        while has_next {
-            match reader.next().transpose() {
-                Ok(batch) => match batch {
-                    Some(batch) => batches.push(batch),
-                    None => {
-                        has_next = false;
+    fn sources(&self) -> Result<&Registry> {
+        self.sources
+            .get()
+            .ok_or_else(|| anyhow!("Data sources are used before the configuration is read"))
+    }
+
+    /// Progress bars only for a human watching stderr
+    ///
+    fn progress(&self) -> bool {
+        !self.args.quiet && atty::is(atty::Stream::Stderr)
+    }
+
+    /// Render profile and write it to output, or stdout
+    ///
+    fn output_helper(&self, profile: &DataFrameMeta, output: Option<&str>, format: Option<&str>) -> Result<()> {
+        let format = match format {
+            Some(f) => f.parse::<OutputFormat>()?,
+            None => output.and_then(OutputFormat::from_path).unwrap_or(OutputFormat::JsonPretty),
+        };
+        let content = reports::render(profile, format)?;
+
+        match output {
+            Some(path) => {
+                self.put_helper(path, content.as_bytes(), format.content_type(), profile.source())?;
+                info!("Profile written to {}", path);
+            }
+            None => println!("{}", content),
+        }
+        Ok(())
+    }
+
+    /// Null tokens from configuration, extended with the ones passed as arguments
+    ///
+    fn null_tokens_helper(&self, config: &Config, tokens: &[String], column_tokens: &[String]) -> Result<NullTokens> {
+        let mut null_tokens = NullTokens::new(config.nulls.tokens.clone());
+        for (column, tokens) in config.nulls.columns.iter() {
+            null_tokens = null_tokens.with_column_tokens(column, tokens.clone());
+        }
+        for token in tokens.iter() {
+            null_tokens = null_tokens.with_token(token);
+        }
+        for pair in column_tokens.iter() {
+            match pair.splitn(2, '=').collect::<Vec<&str>>()[..] {
+                [column, token] => {
+                    null_tokens = null_tokens.with_column_tokens(column, vec![token.to_string()]);
+                }
+                _ => {
+                    return Err(ClientError::Usage(format!("Invalid column null token {} (expected column=token)", pair)).into());
+                }
+            }
+        }
+        Ok(null_tokens)
+    }
+
+    /// Profile previously saved as json, None if input is not one (e.g. a json dataset)
+    /// A json object with a top-level profile key is a saved profile, an error if it does not read as one
+    ///
+    fn saved_profile_helper(input: &str) -> Result<Option<DataFrameMeta>> {
+        if !input.ends_with(".json") || !Path::new(input).is_file() {
+            return Ok(None);
+        }
+        // top-level keys only, values are skipped
+        let keys: HashMap<String, serde::de::IgnoredAny> = match serde_json::from_reader(BufReader::new(File::open(input)?)) {
+            Ok(keys) => keys,
+            Err(_) => return Ok(None),
+        };
+        if !keys.contains_key("profile") {
+            return Ok(None);
+        }
+        let profile = serde_json::from_reader(BufReader::new(File::open(input)?))
+            .with_context(|| format!("{} is not a valid saved profile", input))?;
+        Ok(Some(profile))
+    }
+
+    /// Read a dataset in any supported format, from any source in the registry
+    /// Only the given columns are kept, all if empty
+    ///
+    fn dataset_helper(&self, input: &str, columns: &[String]) -> Result<NcodeDataFrame> {
+        // archive.zip:member reads one member of an archive
+        let (path, member) = match input.find(".zip:") {
+            Some(i) => (&input[..i + 4], Some(&input[i + 5..])),
+            None => (input, None),
+        };
+
+        let source = self.sources()?.open(path)?;
+        if self.args.sample_bytes.is_some() {
+            if member.is_some() {
+                return Err(anyhow!("Cannot sample a member of zip archive {}", path));
+            }
+        } else if source.local_path().is_none()
+            && source.ranged()
+            && InputFormat::from_path(source.name()) == Some(InputFormat::Parquet)
+        {
+            // remote parquet: the footer first, then only the column chunks needed
+            let meta = source.meta()?;
+            if let Some(size) = meta.size {
+                let chunks = RangeChunkReader::new(Arc::from(source), size);
+                let dataframe = self.parquet_helper(input, ParquetReader::new(chunks)?, columns)?;
+                return Ok(dataframe.with_source(meta).with_progress(self.progress()));
+            }
+        }
+
+        let (content, format, meta) = match (self.args.sample_bytes, source.local_path().and_then(|p| p.to_str())) {
+            (Some(bytes), _) => Self::sample_helper(source.as_ref(), bytes)?,
+            (None, Some(path)) => {
+                let (content, format) = self.file_helper(path, member)?;
+                (content, format, source.meta()?)
+            }
+            (None, None) => self.stream_helper(source.as_ref(), member)?,
+        };
+
+        // parquet and ipc files are read from the end, streams must be read whole first
+        let content = match (format, content) {
+            (InputFormat::Parquet, Content::Stream(mut r)) | (InputFormat::Ipc, Content::Stream(mut r)) => {
+                let mut bytes = vec![];
+                r.read_to_end(&mut bytes)?;
+                Content::Bytes(bytes)
+            }
+            (_, content) => content,
+        };
+
+        let mut dataframe = match (format, content) {
+            (InputFormat::Csv, Content::File(f)) => NcodeDataFrame::new(self.csv_helper(f)?),
+            (InputFormat::Csv, Content::Bytes(b)) => NcodeDataFrame::new(self.csv_helper(Cursor::new(b))?),
+            (InputFormat::Csv, Content::Stream(r)) => self.csv_stream_helper(input, r)?,
+            (InputFormat::Parquet, Content::File(f)) => self.parquet_helper(input, ParquetReader::new(f)?, columns)?,
+            (InputFormat::Parquet, Content::Bytes(b)) => {
+                self.parquet_helper(input, ParquetReader::new(SliceableCursor::new(b))?, columns)?
+            }
+            (InputFormat::Parquet, Content::Stream(_)) | (InputFormat::Ipc, Content::Stream(_)) => unreachable!(),
+            (InputFormat::Json, content) => {
+                let (schema, batch) = match content {
+                    Content::File(f) => JsonReader::new(BufReader::new(f)).finish()?,
+                    Content::Bytes(b) => JsonReader::new(&b[..]).finish()?,
+                    Content::Stream(r) => JsonReader::new(r).finish()?,
+                };
+                for (field, present) in schema.partial_fields() {
+                    warn!("{} is present in {} of {} records", field, present, schema.records);
+                }
+                NcodeDataFrame::from_record_batches(batch.schema(), vec![batch])?.with_json_schema(&schema)
+            }
+            (InputFormat::Ipc, content) => {
+                let (schema, batches) = match content {
+                    Content::File(f) => ipc_format::read_file(f)?,
+                    Content::Bytes(b) => ipc_format::read_file(Cursor::new(b))?,
+                    Content::Stream(_) => unreachable!(),
+                };
+                self.frame_helper(input, schema, batches)?
+            }
+            (InputFormat::IpcStream, content) => {
+                let (schema, batches) = match content {
+                    Content::File(f) => ipc_format::read_stream(BufReader::new(f))?,
+                    Content::Bytes(b) => ipc_format::read_stream(&b[..])?,
+                    Content::Stream(r) => ipc_format::read_stream(r)?,
+                };
+                self.frame_helper(input, schema, batches)?
+            }
+        };
+        // parquet only decoded the selected columns
+        if !columns.is_empty() && format != InputFormat::Parquet {
+            dataframe = dataframe.select(columns)?;
+        }
+        Ok(dataframe.with_source(meta).with_progress(self.progress()))
+    }
+
+    /// Local file, decompressed on the fly, and its format
+    ///
+    fn file_helper(&self, path: &str, member: Option<&str>) -> Result<(Content, InputFormat)> {
+        Ok(match Compression::detect(path)? {
+            Compression::None => (Content::File(File::open(path)?), InputFormat::detect(path)?),
+            Compression::Zip => {
+                let member = match member {
+                    Some(m) => m.to_string(),
+                    None => match &compression::zip_members(File::open(path)?)?[..] {
+                        [m] => m.clone(),
+                        members => {
+                            return Err(anyhow!("{} has {} members, pick one as {}:<member>", path, members.len(), path))
+                        }
+                    },
+                };
+                let bytes = compression::read_zip_member(File::open(path)?, &member)?;
+                let format = Self::content_format(&member, &bytes);
+                (Content::Bytes(bytes), format)
+            }
+            c => {
+                let mut reader = BufReader::new(compression::decoder(BufReader::new(File::open(path)?), c)?);
+                let format = Self::content_format(compression::strip_extension(path), reader.fill_buf()?);
+                (Content::Stream(Box::new(reader)), format)
+            }
+        })
+    }
+
+    /// Content of a source that is not a local file (stdin, bucket, url, ...), decompressed on the fly
+    /// Compression is detected from the first bytes, the format from the name of the source,
+    /// its content type or the content
+    ///
+    fn stream_helper(&self, source: &dyn DataSource, member: Option<&str>) -> Result<(Content, InputFormat, SourceMeta)> {
+        let (reader, meta) = source.open()?;
+        debug!("Reading {} ({:?})", source.uri(), meta);
+        let mut reader: Box<dyn BufRead> = Box::new(BufReader::new(reader));
+        match Compression::from_magic(reader.fill_buf()?) {
+            Compression::None => {}
+            Compression::Zip => {
+                let mut bytes = vec![];
+                reader.read_to_end(&mut bytes)?;
+                let bytes = match member {
+                    Some(m) => compression::read_zip_member(Cursor::new(&bytes), m)?,
+                    None => compression::decompress(bytes, Compression::Zip)?,
+                };
+                let format = Self::content_format(member.unwrap_or(""), &bytes);
+                return Ok((Content::Bytes(bytes), format, meta));
+            }
+            c => reader = Box::new(BufReader::new(compression::decoder(reader, c)?)),
+        }
+        let format = Self::source_format(source, &meta, reader.fill_buf()?);
+        Ok((Content::Stream(reader), format, meta))
+    }
+
+    /// First bytes of a source only, e.g. to profile the head of a huge object
+    /// Sources supporting it are read with a ranged request; compressed content is decoded
+    /// as far as it goes and the last, likely partial, line is dropped
+    ///
+    fn sample_helper(source: &dyn DataSource, bytes: u64) -> Result<(Content, InputFormat, SourceMeta)> {
+        let (mut reader, meta) = if source.ranged() {
+            (source.open_range(0, Some(bytes))?, source.meta()?)
+        } else {
+            let (reader, meta) = source.open()?;
+            (Box::new(reader.take(bytes)) as Box<dyn Read>, meta)
+        };
+        let mut head = vec![];
+        reader.read_to_end(&mut head)?;
+
+        let content = match Compression::from_magic(&head) {
+            Compression::None => head,
+            Compression::Zip => return Err(anyhow!("Cannot sample zip archive {}", source.uri())),
+            c => {
+                // a truncated stream ends early, keep what was decoded before it
+                // any other error (corrupt content) is reported
+                let mut decoder = compression::decoder(&head[..], c)?;
+                let mut content = vec![];
+                let mut buffer = [0u8; 64 * 1024];
+                loop {
+                    match decoder.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(n) => content.extend_from_slice(&buffer[..n]),
+                        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                        Err(e) => return Err(anyhow!("Cannot decode the first bytes of {}: {}", source.uri(), e)),
                     }
-                },
+                }
+                content
+            }
+        };
+        let format = Self::source_format(source, &meta, &content);
+        if format != InputFormat::Csv && format != InputFormat::Json {
+            return Err(anyhow!("Cannot sample {} ({:?}), only csv and json lines are read partially", source.uri(), format));
+        }
+        let mut content = content;
+        if let Some(end) = content.iter().rposition(|b| *b == b'\n') {
+            content.truncate(end + 1);
+        }
+        info!("Reading the first {} bytes of {}", content.len(), source.uri());
+        Ok((Content::Bytes(content), format, meta))
+    }
+
+    /// Format of a source from its name (without compression extension), its content type or its first bytes
+    ///
+    fn source_format(source: &dyn DataSource, meta: &SourceMeta, head: &[u8]) -> InputFormat {
+        let name = compression::strip_extension(source.name());
+        InputFormat::from_path(name)
+            .or_else(|| meta.content_type.as_deref().and_then(InputFormat::from_content_type))
+            .unwrap_or_else(|| Self::content_format(name, head))
+    }
+
+    /// Read csv from a stream into record batches, buffering only the head for schema inference
+    /// Unlike files, the stream cannot be read twice: columns with values that do not parse
+    /// as inferred from the head are read as text
+    ///
+    fn csv_stream_helper<R: Read>(&self, input: &str, reader: R) -> Result<NcodeDataFrame> {
+        let (schema, batches) = csvr::new(reader).infer_schema(1000).has_header(true).read()?;
+        self.frame_helper(input, schema, batches)
+    }
 
-                Err(_) => {
-                    has_next = false;
+    /// Format of decompressed content, from the name it had in the archive or its first bytes
+    ///
+    fn content_format(name: &str, content: &[u8]) -> InputFormat {
+        InputFormat::from_path(name)
+            .or_else(|| InputFormat::from_magic(&content[..content.len().min(64)]))
+            .unwrap_or(InputFormat::Csv)
+    }
+
+    /// Inputs to profile one by one: each object under a prefix (s3://bucket/drops/) passing filter,
+    /// each member of a zip archive, or input itself
+    ///
+    fn sources_helper(&self, input: &str, filter: &ListFilter) -> Result<Vec<String>> {
+        if input.contains("://") && input.ends_with('/') {
+            let objects = self.sources()?.list(input, filter)?;
+            if objects.is_empty() {
+                return Err(anyhow!("No objects under {} matching the filters", input));
+            }
+            info!("{} object(s) under {}", objects.len(), input);
+            return Ok(objects.into_iter().map(|o| o.uri).collect());
+        }
+        let source = self.sources()?.open(input)?;
+        let path = match source.local_path().and_then(|p| p.to_str()) {
+            Some(path) if !input.contains(".zip:") && Compression::detect(path)? == Compression::Zip => path,
+            _ => return Ok(vec![input.to_string()]),
+        };
+        let members = compression::zip_members(File::open(path)?)?;
+        if members.len() == 1 {
+            return Ok(vec![input.to_string()]);
+        }
+        Ok(members.iter().map(|m| format!("{}:{}", input, m)).collect())
+    }
+
+    /// Filters of a listing given on the command line
+    ///
+    fn list_filter_helper(&self, listing: &Listing) -> Result<ListFilter> {
+        let mut filter = ListFilter::default();
+        if let Some(glob) = &listing.glob {
+            filter = filter.with_glob(glob)?;
+        }
+        if let Some(suffix) = &listing.suffix {
+            filter = filter.with_suffix(suffix);
+        }
+        if let Some(since) = &listing.modified_since {
+            filter = filter.with_modified_since(since)?;
+        }
+        Ok(filter)
+    }
+
+    /// Output of each input, output itself when there is a single one
+    /// Fails when two inputs would be written to the same path
+    ///
+    fn member_outputs_helper(output: Option<&str>, root: &str, sources: &[String]) -> Result<Vec<Option<String>>> {
+        let output = match output {
+            Some(output) if sources.len() > 1 => output,
+            other => return Ok(vec![other.map(String::from); sources.len()]),
+        };
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        sources
+            .iter()
+            .map(|source| {
+                let path = member_output(output, root, source);
+                if let Some(other) = seen.insert(path.clone(), source) {
+                    return Err(ClientError::Usage(format!("{} and {} would both be written to {}", other, source, path)).into());
+                }
+                Ok(Some(path))
+            })
+            .collect()
+    }
+
+    /// Partitions of a dataset (objects under a prefix, files of an archive) read as one
+    ///
+    fn partitioned_dataset_helper(&self, input: &str, partitions: &[String], columns: &[String]) -> Result<NcodeDataFrame> {
+        let mut frames: Vec<NcodeDataFrame> = vec![];
+        for partition in partitions.iter() {
+            debug!("Reading partition {}", partition);
+            frames.push(self.dataset_helper(partition, columns)?);
+        }
+        let source = SourceMeta {
+            uri: input.to_string(),
+            ..Default::default()
+        };
+        Ok(NcodeDataFrame::concat(frames)?.with_source(source).with_progress(self.progress()))
+    }
+
+    fn parquet_helper<R: ChunkReader + 'static>(
+        &self,
+        input: &str,
+        reader: ParquetReader<R>,
+        columns: &[String],
+    ) -> Result<NcodeDataFrame> {
+        let reader = if columns.is_empty() { reader } else { reader.with_columns(columns.to_vec()) };
+        let statistics = reader.statistics()?;
+        let (schema, batches) = reader.finish()?;
+        Ok(self.frame_helper(input, schema, batches)?.with_statistics(statistics))
+    }
+
+    /// Dataframe over the record batches read from input, without copying them
+    ///
+    fn frame_helper(&self, input: &str, schema: Arc<Schema>, batches: Vec<RecordBatch>) -> Result<NcodeDataFrame> {
+        if batches.is_empty() {
+            return Err(anyhow!("No rows in {}", input));
+        }
+        debug!("{} record batch(es) read from {}", batches.len(), input);
+        Ok(NcodeDataFrame::from_record_batches(schema, batches)?)
+    }
+
+    /// Like dataset_helper, reading local csv input (possibly compressed) from an arrow IPC copy next to it (input.arrow)
+    /// The copy is written on first use and whenever the csv is newer
+    ///
+    fn cached_dataset_helper(&self, input: &str, columns: &[String]) -> Result<NcodeDataFrame> {
+        // compressed csv is cached too, members of archives and remote sources are not
+        let source = self.sources()?.open(input)?;
+        let path = match source.local_path().and_then(|p| p.to_str()) {
+            Some(path) if !input.contains(".zip:") && self.args.sample_bytes.is_none() => path,
+            _ => return self.dataset_helper(input, columns),
+        };
+        if InputFormat::from_path(compression::strip_extension(path)) != Some(InputFormat::Csv) {
+            return self.dataset_helper(input, columns);
+        }
+        let cache = format!("{}.arrow", path);
+        let fresh = match (std::fs::metadata(path)?.modified(), std::fs::metadata(&cache).and_then(|m| m.modified())) {
+            (Ok(modified), Ok(cached)) => cached >= modified,
+            _ => false,
+        };
+        if fresh {
+            debug!("Reading {} from cache {}", input, cache);
+            return Ok(self.dataset_helper(&cache, columns)?.with_source(source.meta()?));
+        }
+
+        let dataframe = self.dataset_helper(input, &[])?;
+        // the cache is an optimization: a read-only directory or a full disk do not fail the command
+        match File::create(&cache) {
+            Ok(file) => {
+                let frame = dataframe.to_frame()?;
+                match ipc_format::write_file(file, frame.schema(), &[frame.to_record_batch()?]) {
+                    Ok(()) => info!("Cached {} as {}", input, cache),
+                    Err(e) => {
+                        warn!("Cannot cache {} as {}: {}", input, cache, e);
+                        // a partial copy would be read as fresh next time
+                        let _ = std::fs::remove_file(&cache);
+                    }
                 }
             }
+            Err(e) => warn!("Cannot cache {} as {}: {}", input, cache, e),
+        }
+        if columns.is_empty() {
+            Ok(dataframe)
+        } else {
+            Ok(dataframe.select(columns)?)
         }
-        // println!("batches {:?}", batches);
-        let schema = batches[0].schema();
-        // println!("single batch schema: {} ", schema);
-        // println!("num batches: {}", batches.len());
-        let df = DataFrame::from_record_batches(schema, batches);
-        df
+    }
+
+    /// Write data to output, as an arrow IPC file or stream, or csv, from its extension
+    ///
+    fn write_data_helper(&self, dataframe: &NcodeDataFrame, output: &str, source: Option<&SourceMeta>) -> Result<()> {
+        let frame = dataframe.to_frame()?;
+        let batches = [frame.to_record_batch()?];
+        let mut content: Vec<u8> = vec![];
+        let content_type = match InputFormat::from_path(output) {
+            Some(InputFormat::Ipc) => {
+                ipc_format::write_file(&mut content, frame.schema(), &batches)?;
+                "application/vnd.apache.arrow.file"
+            }
+            Some(InputFormat::IpcStream) => {
+                ipc_format::write_stream(&mut content, frame.schema(), &batches)?;
+                "application/vnd.apache.arrow.stream"
+            }
+            Some(InputFormat::Csv) => {
+                csv_format::write_csv(&mut content, &batches)?;
+                "text/csv"
+            }
+            _ => return Err(anyhow!("Cannot write {} (expected .arrow, .feather, .arrows or .csv)", output)),
+        };
+        self.put_helper(output, &content, content_type, source)?;
+        info!("{} rows written to {}", frame.num_rows(), output);
+        Ok(())
+    }
+
+    /// Write an artifact to a local path or a bucket (s3://bucket/key)
+    /// With --tag-source, objects are tagged with the uri and fingerprint of the data they come from
+    ///
+    fn put_helper(&self, output: &str, content: &[u8], content_type: &str, source: Option<&SourceMeta>) -> Result<()> {
+        let tags = match (self.args.tag_source, source) {
+            (true, Some(source)) => sink::source_tags(source),
+            _ => vec![],
+        };
+        self.sources()?.create(output)?.put(content, content_type, &tags)?;
+        Ok(())
+    }
+
+    /// Read csv into a polars dataframe
+    ///
+    fn csv_helper<R: Read + Seek + Send + Sync + 'static>(&self, reader: R) -> Result<polars::prelude::DataFrame> {
+        Ok(CsvReader::new(reader)
+            .infer_schema(None)
+            .has_header(true)
+            .finish()?)
     }
 
     fn login_helper(
         &self,
         url: String,
         payload: serde_json::Value,
-    ) -> Result<String, reqwest::Error> {
+    ) -> Result<String> {
         let endpoint = format!("{}/auth/login", url);
         let http_client = reqwest::blocking::ClientBuilder::new().build()?;
         let response = http_client.post(&endpoint).json(&payload).send();
@@ -502,20 +1130,19 @@ impl Frontend {
                 let mut result = String::new();
                 if res.status() == reqwest::StatusCode::OK {
                     let auth_token = res.json::<HashMap<String, String>>()?;
-                    result = auth_token["Authorization"].to_string();
+                    result = auth_token
+                        .get("Authorization")
+                        .cloned()
+                        .ok_or_else(|| ClientError::Remote(String::from("No token in the login response")))?;
 
                 } else {
-                    println!("Response not 200 OK :(");
-                    process::exit(-1);
+                    return Err(ClientError::Remote(format!("Login failed: {}", res.status())).into());
                 }
-                println!("Successfully logged in!\nWelcome to ncode!\n\n");
+                info!("Successfully logged in! Welcome to ncode!");
                 Ok(result)
             }
 
-            Err(e) => {
-                println!("Could not make the request! (Oops..)");
-                Err(e)
-            }
+            Err(e) => Err(ClientError::Remote(format!("Could not make the request! ({})", e)).into()),
         }
     }
 
@@ -536,23 +1163,21 @@ impl Frontend {
         match response {
             Ok(res) => {
                 if res.status() == reqwest::StatusCode::OK {
-                    println!("status ok ");
+                    debug!("status ok ");
                     let str_assets: String = res.text()?;
                     // let assets: String = res.json()?;
                     result.insert("status".to_string(), "success".to_string());
                     result.insert("message".to_string(), str_assets.clone());
 
                 } else {
-                    println!("Status not ok");
+                    error!("Status not ok");
                     // response = res.text()?;
                     result.insert("status".to_string(), "failed".to_string());
                     result.insert("message".to_string(), res.text()?);
                 }
             }
 
-            Err(e) => {
-                println!("Could not make request! {:?} ", e);
-            }
+            Err(e) => return Err(ClientError::Remote(format!("Could not make request to {}: {}", endpoint, e)).into()),
         }
         Ok(result)
     }
@@ -572,23 +1197,21 @@ impl Frontend {
         match response {
             Ok(res) => {
                 if res.status() == reqwest::StatusCode::OK {
-                    println!("status ok ");
+                    debug!("status ok ");
                     let str_assets: String = res.text()?;
                     // let assets: String = res.json()?;
                     result.insert("status".to_string(), "success".to_string());
                     result.insert("message".to_string(), str_assets.clone());
 
                 } else {
-                    println!("Status not ok");
+                    error!("Status not ok");
                     // response = res.text()?;
                     result.insert("status".to_string(), "failed".to_string());
                     result.insert("message".to_string(), res.text()?);
                 }
             }
 
-            Err(e) => {
-                println!("Could not make request! {:?} ", e);
-            }
+            Err(e) => return Err(ClientError::Remote(format!("Could not make request to {}: {}", endpoint, e)).into()),
         }
         Ok(result)
     }
@@ -623,14 +1246,137 @@ impl Frontend {
 
                 } else if res.status() == reqwest::StatusCode::CREATED {
                     result = res.json()?;
+                } else {
+                    return Err(ClientError::Remote(format!("{} answered {}", endpoint, res.status())).into());
                 }
             }
 
-            Err(e) => {
-                println!("Could not make request! {:?} ", e);
-            }
+            Err(e) => return Err(ClientError::Remote(format!("Could not make request to {}: {}", endpoint, e)).into()),
         }
 
         Ok(result)
     }
 }
+
+/// Error with the message of the server unless the status of a response is success
+///
+fn success(response: &HashMap<String, String>, what: &str) -> Result<()> {
+    match response.get("status").map(String::as_str) {
+        Some("success") => Ok(()),
+        _ => {
+            let message = response.get("message").map(String::as_str).unwrap_or_default();
+            Err(ClientError::Remote(format!("{}. Status not OK: {}", what, message)).into())
+        }
+    }
+}
+
+/// Output path for one of several inputs: profile.json becomes profile.member.json,
+/// member being the path of the input under root (a prefix or an archive) without extensions,
+/// / replaced by _ (2020-12-01/cards.csv.gz gives 2020-12-01_cards)
+///
+fn member_output(output: &str, root: &str, source: &str) -> String {
+    let relative = source
+        .strip_prefix(root)
+        .unwrap_or(source)
+        .trim_start_matches(|c| c == ':' || c == '/');
+    let relative = Path::new(compression::strip_extension(relative));
+    let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let stem = match relative.parent().and_then(|p| p.to_str()).filter(|p| !p.is_empty()) {
+        Some(parent) => format!("{}_{}", parent.replace('/', "_"), stem),
+        None => stem.to_string(),
+    };
+    let path = Path::new(output);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => path.with_extension(format!("{}.{}", stem, extension)).to_string_lossy().to_string(),
+        None => format!("{}.{}", output, stem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_configuration_uses_default_storage() {
+        let config: Config = toml::from_str(CONFIG_SAMPLE_CONTENT).unwrap();
+        assert_eq!(config.storage.default, Default::default());
+        assert!(config.storage.profiles.is_empty());
+    }
+
+    #[test]
+    fn member_outputs_from_relative_paths() {
+        let sources = vec![
+            String::from("s3://bucket/drops/2020-12-01/cards.csv.gz"),
+            String::from("s3://bucket/drops/2020-12-02/cards.csv.gz"),
+        ];
+        let outputs = Frontend::member_outputs_helper(Some("profile.json"), "s3://bucket/drops/", &sources).unwrap();
+        assert_eq!(
+            outputs,
+            vec![
+                Some(String::from("profile.2020-12-01_cards.json")),
+                Some(String::from("profile.2020-12-02_cards.json"))
+            ]
+        );
+        assert_eq!(member_output("report", "data.zip", "data.zip:cards.csv"), "report.cards");
+
+        let sources = vec![String::from("data.zip:cards.csv"), String::from("data.zip:cards.json")];
+        assert!(Frontend::member_outputs_helper(Some("profile.json"), "data.zip", &sources).is_err());
+        assert_eq!(Frontend::member_outputs_helper(None, "data.zip", &sources).unwrap(), vec![None, None]);
+    }
+
+    #[test]
+    fn saved_profile_or_json_dataset() {
+        let path = std::env::temp_dir().join("xtract_saved_profile.json");
+        let input = path.to_str().unwrap();
+
+        std::fs::write(&path, r#"[{"profile": 1}, {"profile": 2}]"#).unwrap();
+        assert!(Frontend::saved_profile_helper(input).unwrap().is_none());
+        std::fs::write(&path, r#"{"id": 1, "name": "a"}"#).unwrap();
+        assert!(Frontend::saved_profile_helper(input).unwrap().is_none());
+        // a profile that does not read is reported, not profiled as data
+        std::fs::write(&path, r#"{"datasource": "a.csv", "profile": {}}"#).unwrap();
+        assert!(Frontend::saved_profile_helper(input).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sample_first_bytes() {
+        use flate2::write::GzEncoder;
+        use xtract::loaders::source::FileSource;
+
+        let csv: String = (0..2000).map(|i| format!("{},name{}\n", i, i)).collect();
+        let dir = std::env::temp_dir();
+
+        let path = dir.join("xtract_sample.csv");
+        std::fs::write(&path, &csv).unwrap();
+        let (content, format, _) = Frontend::sample_helper(&FileSource::new(path.to_str().unwrap()), 100).unwrap();
+        assert!(format == InputFormat::Csv);
+        match content {
+            // the partial last line is dropped
+            Content::Bytes(b) => assert_eq!(b, csv.as_bytes()[..b.len()].to_vec()),
+            _ => panic!("expected bytes"),
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        // a truncated gzip stream is decoded as far as it goes
+        let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(csv.as_bytes()).unwrap();
+        let gz = encoder.finish().unwrap();
+        let path = dir.join("xtract_sample.csv.gz");
+        std::fs::write(&path, &gz).unwrap();
+        let source = FileSource::new(path.to_str().unwrap());
+        match Frontend::sample_helper(&source, gz.len() as u64 / 2).unwrap().0 {
+            Content::Bytes(b) => {
+                assert!(!b.is_empty() && b.len() < csv.len());
+                assert!(b.ends_with(b"\n") && csv.starts_with(std::str::from_utf8(&b).unwrap()));
+            }
+            _ => panic!("expected bytes"),
+        }
+
+        // corrupt content is an error, not an empty sample
+        let mut corrupt = gz[..10].to_vec();
+        corrupt.extend(std::iter::repeat(0xff).take(100));
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(Frontend::sample_helper(&source, 1000).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
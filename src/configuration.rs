@@ -1,16 +1,24 @@
+use std::collections::HashMap;
 use std::fs::File;
+use std::fmt;
 use std::io::prelude::*;
-use std::io::Error;
 use toml;
 use serde_derive::Deserialize;
 
+use crate::loaders::nulls::DEFAULT_NULL_TOKENS;
+
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub api: Api,
     pub credentials: Credentials,
-    pub settings: Settings
-
+    pub settings: Settings,
+    #[serde(default)]
+    pub nulls: Nulls,
+    #[serde(default)]
+    pub http: Http,
+    #[serde(default)]
+    pub storage: Storage,
 }
 
 #[derive(Deserialize, Debug)]
@@ -32,24 +40,156 @@ pub struct Settings {
 }
 
 
-pub fn get_content_from_file(filepath: &str) -> Result<String, Error> {
-    let mut file = match File::open(filepath) {
-        Ok(f) => f,
-        Err(err) => {
-            println!("{} (~/.ncode/configuration.toml)", err);
-            println!("Please create a configuration file under ~/.ncode/configuration.toml");
-            std::process::exit(1);
+/// Tokens that encode missing values in input data
+/// `tokens` apply to all columns, `columns` maps a column name to its own extra tokens
+#[derive(Deserialize, Debug)]
+pub struct Nulls {
+    #[serde(default = "default_null_tokens")]
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub columns: HashMap<String, Vec<String>>,
+}
+
+impl Default for Nulls {
+    fn default() -> Self {
+        Nulls {
+            tokens: default_null_tokens(),
+            columns: HashMap::new(),
         }
-    };
+    }
+}
+
+/// Object storage (s3 and compatible): [storage] is the default profile,
+/// [storage.profiles.<name>] are named ones, e.g. for buckets in another account or a local minio
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct Storage {
+    #[serde(flatten)]
+    pub default: StorageProfile,
+    #[serde(default)]
+    pub profiles: HashMap<String, StorageProfile>,
+}
+
+/// Settings left out are taken from the environment (AWS_ENDPOINT_URL, AWS_REGION, ...)
+/// and credentials from the standard AWS chain (environment, ~/.aws/credentials, instance role)
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct StorageProfile {
+    /// Endpoint, e.g. http://localhost:9000, the one of the region if not set
+    pub url: Option<String>,
+    pub region: Option<String>,
+    pub access_key: Option<String>,
+    pub secret_access_key: Option<String>,
+    /// Buckets read with this profile when no profile is given
+    #[serde(default)]
+    pub buckets: Vec<String>,
+    /// Buckets are addressed as endpoint/bucket/key, false (bucket.endpoint/key) is rejected
+    pub path_style: Option<bool>,
+}
+
+impl Storage {
+    /// Reason the storage settings cannot be used, if any
+    ///
+    pub fn check(&self) -> Result<(), String> {
+        let profiles = std::iter::once(("storage", &self.default))
+            .chain(self.profiles.iter().map(|(name, profile)| (name.as_str(), profile)));
+        for (name, profile) in profiles {
+            if profile.path_style == Some(false) {
+                return Err(format!(
+                    "path_style = false in {}, only path style buckets (endpoint/bucket/key) are supported",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Profile with this name, or the profile listing the bucket, or the default one
+    ///
+    pub fn profile(&self, name: Option<&str>, bucket: &str) -> Option<&StorageProfile> {
+        match name {
+            Some(name) => self.profiles.get(name),
+            None => Some(
+                self.profiles
+                    .values()
+                    .find(|p| p.buckets.iter().any(|b| b == bucket))
+                    .unwrap_or(&self.default),
+            ),
+        }
+    }
+}
+
+/// Credentials for hosts serving input data over http(s), by host name
+#[derive(Deserialize, Debug, Default)]
+pub struct Http {
+    #[serde(default)]
+    pub hosts: HashMap<String, HttpAuth>,
+}
+
+/// A token is sent as bearer auth, otherwise username and password as basic auth
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HttpAuth {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+fn default_null_tokens() -> Vec<String> {
+    DEFAULT_NULL_TOKENS.iter().map(|t| t.to_string()).collect()
+}
+
 
+/// Why the configuration (or another file of ~/.ncode, like the token) could not be read
+#[derive(Debug)]
+pub enum ConfigError {
+    /// File missing or unreadable
+    Io { path: String, error: std::io::Error },
+    /// Not valid toml, or settings missing
+    Parse { path: String, error: toml::de::Error },
+    /// Valid toml with a setting that is not supported
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => write!(f, "Cannot read {}: {}", path, error),
+            ConfigError::Parse { path, error } => write!(f, "Invalid configuration {}: {}", path, error),
+            ConfigError::Invalid { path, message } => write!(f, "Invalid configuration {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { error, .. } => Some(error),
+            ConfigError::Parse { error, .. } => Some(error),
+            ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+pub fn get_content_from_file(filepath: &str) -> Result<String, ConfigError> {
+    let io_error = |error| ConfigError::Io {
+        path: filepath.to_string(),
+        error,
+    };
     let mut content = String::new();
-    file.read_to_string(&mut content)?;
+    File::open(filepath)
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .map_err(io_error)?;
     Ok(content)
 }
 
-pub fn get_configuration_from_file(filepath: &str) -> Result<Config, Error> {
+pub fn get_configuration_from_file(filepath: &str) -> Result<Config, ConfigError> {
     let configuration = get_content_from_file(filepath)?;
-    let config: Config = toml::from_str(configuration.as_str()).unwrap();
+    let config: Config = toml::from_str(configuration.as_str()).map_err(|error| ConfigError::Parse {
+        path: filepath.to_string(),
+        error,
+    })?;
+    config.storage.check().map_err(|message| ConfigError::Invalid {
+        path: filepath.to_string(),
+        message,
+    })?;
 
     Ok(
         Config {
@@ -65,7 +205,75 @@ pub fn get_configuration_from_file(filepath: &str) -> Result<Config, Error> {
 
             settings: Settings {
                 // tokenfile: config.settings.tokenfile
-            }
+            },
+
+            nulls: config.nulls,
+
+            http: config.http,
+
+            storage: config.storage,
         }
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_storage_profile() {
+        let config: Config = toml::from_str(
+            r#"
+            [api]
+            server = "localhost"
+            port = 5000
+
+            [credentials]
+            username = "username"
+            password = "password"
+
+            [settings]
+
+            [storage]
+            region = "us-east-1"
+
+            [storage.profiles.landing]
+            region = "eu-west-1"
+            buckets = ["landing-zone"]
+
+            [storage.profiles.minio]
+            url = "http://localhost:9000"
+            path_style = true
+            "#,
+        )
+        .unwrap();
+        let storage = config.storage;
+        let region = |p: Option<&StorageProfile>| p.and_then(|p| p.region.clone());
+
+        assert_eq!(storage.default.region.as_deref(), Some("us-east-1"));
+        assert_eq!(storage.profiles.len(), 2);
+        // by name, whatever the bucket
+        assert_eq!(
+            storage.profile(Some("minio"), "landing-zone").and_then(|p| p.url.as_deref()),
+            Some("http://localhost:9000")
+        );
+        assert!(storage.profile(Some("missing"), "landing-zone").is_none());
+        // by bucket, then default
+        assert_eq!(region(storage.profile(None, "landing-zone")), Some(String::from("eu-west-1")));
+        assert_eq!(region(storage.profile(None, "other")), Some(String::from("us-east-1")));
+        assert!(storage.check().is_ok());
+    }
+
+    #[test]
+    fn reject_virtual_hosted_buckets() {
+        let storage: Storage = toml::from_str(
+            r#"
+            [profiles.aws]
+            path_style = false
+            "#,
+        )
+        .unwrap();
+        let message = storage.check().unwrap_err();
+        assert!(message.contains("path_style = false in aws"), "{}", message);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::expectations::validate::type_name;
+use crate::expectations::{ColumnExpectation, ExpectationSuite, TableExpectation};
+use crate::loaders::dataframe::{Column, ColumnFeatures, ColumnType, DataFrameMeta};
+
+/// Minimum share of non null values of a semantic type for it to become an expectation
+const SEMANTIC_TYPE_RATIO: f64 = 0.9;
+
+/// Semantic type (other than Unknown) found for most values of column, with its ratio
+///
+pub(crate) fn dominant_semantic_type(column: &Column) -> Option<(ColumnType, f64)> {
+    let total: usize = column.types().values().sum();
+    if total == 0 {
+        return None;
+    }
+    column
+        .types()
+        .iter()
+        .filter(|(t, _)| **t != ColumnType::Unknown)
+        .max_by_key(|(_, count)| **count)
+        .map(|(t, count)| (*t, *count as f64 / total as f64))
+        .filter(|(_, ratio)| *ratio >= SEMANTIC_TYPE_RATIO)
+}
+
+fn column_expectation(name: &str, column: &Column, tolerance: f64) -> ColumnExpectation {
+    let mut expectation = ColumnExpectation {
+        name: name.to_string(),
+        required: true,
+        dtype: column.dtype().map(|t| type_name(t).to_string()),
+        ..Default::default()
+    };
+
+    // nullability
+    if column.null_count() == 0 {
+        expectation.not_null = true;
+    } else {
+        let ratio = column.null_count() as f64 / column.count() as f64;
+        expectation.max_null_ratio = Some((ratio * (1.0 + tolerance)).min(1.0));
+    }
+
+    // every value seen once
+    if column.null_count() == 0 && column.nunique() == column.count() {
+        expectation.unique = true;
+    }
+
+    // observed range widened by tolerance
+    if let ColumnFeatures::Numeric(numeric) = column.features() {
+        if let (Some(min), Some(max)) = (numeric.min(), numeric.max()) {
+            let margin = (max - min) * tolerance;
+            expectation.min = Some(min - margin);
+            expectation.max = Some(max + margin);
+        }
+    }
+
+    // allowed values only when the profile has seen all of them
+    let top_values = column.top_values();
+    let nunique = column.nunique().saturating_sub((column.null_count() > 0) as usize);
+    if column.is_categorical() && !top_values.is_empty() && top_values.len() >= nunique {
+        let mut values: Vec<String> = top_values.iter().map(|v| v.value.clone()).collect();
+        values.sort();
+        expectation.values = Some(values);
+    }
+
+    if let Some((semantic_type, ratio)) = dominant_semantic_type(column) {
+        expectation.semantic_type = Some(format!("{:?}", semantic_type));
+        expectation.min_semantic_ratio = Some((ratio - tolerance).max(0.0));
+    }
+
+    expectation
+}
+
+/// Starter expectations from the profile of a dataset
+/// Numeric ranges and null ratios are relaxed by tolerance (e.g. 0.1 for 10%)
+///
+pub fn generate_suite(profile: &DataFrameMeta, tolerance: f64) -> ExpectationSuite {
+    let names = profile.get_column_names();
+
+    let columns = names
+        .iter()
+        .filter_map(|name| profile.column(name).map(|column| column_expectation(name, column, tolerance)))
+        .collect();
+
+    ExpectationSuite {
+        table: TableExpectation {
+            min_rows: Some(1),
+            max_rows: None,
+        },
+        columns,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaders::dataframe::NcodeDataFrame;
+    use polars::prelude::{DataFrame, Series};
+
+    #[test]
+    fn suite_from_profile() {
+        let ids: Vec<i64> = (0..11).collect();
+        let rates: Vec<f64> = (0..11).map(|i| if i % 2 == 0 { 0.5 } else { 1.5 }).collect();
+        let names: Vec<Option<String>> = (0..11).map(|i| if i == 0 { None } else { Some(format!("n{}", i)) }).collect();
+        let columns = vec![
+            Series::new("id", &ids),
+            Series::new("rate", &rates),
+            Series::new("name", &names.iter().map(|n| n.as_deref()).collect::<Vec<Option<&str>>>()),
+        ];
+        let profile = NcodeDataFrame::new(DataFrame::new(columns).unwrap())
+            .with_progress(false)
+            .profile()
+            .unwrap();
+
+        let suite = generate_suite(&profile, 0.1);
+        assert_eq!(suite.table.min_rows, Some(1));
+        let id = &suite.columns[0];
+        assert!(id.unique && id.not_null);
+        assert_eq!((id.min, id.max), (Some(-1.0), Some(11.0)));
+        assert_eq!(id.values, None);
+
+        // float values are counted like any other, so a categorical float column gets its values
+        let rate = &suite.columns[1];
+        assert!(!rate.unique);
+        assert_eq!(rate.values, Some(vec![String::from("0.5"), String::from("1.5")]));
+
+        let name = &suite.columns[2];
+        assert!(!name.not_null);
+        assert!((name.max_null_ratio.unwrap() - 1.1 / 11.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,94 @@
+// Declarative data quality expectations, read from a toml file
+//
+// [table]
+// min_rows = 1
+// max_rows = 1000000
+//
+// [[columns]]
+// name = "amount"
+// type = "Float"
+// not_null = true
+// min = 0.0
+// max = 10000.0
+// max_null_ratio = 0.01
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+pub mod generate;
+pub mod validate;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TableExpectation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_rows: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<usize>,
+}
+
+/// Expectations on a single column
+/// Every expectation except `name` is optional
+///
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ColumnExpectation {
+    pub name: String,
+    /// Column must exist (default true)
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// One of Int, Float, Str, Bool
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub dtype: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub not_null: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unique: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Allowed values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+    /// Every value must match this regular expression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_null_ratio: Option<f64>,
+    /// Detected semantic type of values, one of Email, Iban
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_type: Option<String>,
+    /// Minimum ratio of non null values of the semantic type (default 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_semantic_ratio: Option<f64>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExpectationSuite {
+    #[serde(default)]
+    pub table: TableExpectation,
+    #[serde(default)]
+    pub columns: Vec<ColumnExpectation>,
+}
+
+impl ExpectationSuite {
+    pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    pub fn from_file(filepath: &str) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(filepath)?;
+        Ok(Self::from_toml(&content)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+}
@@ -0,0 +1,421 @@
+use arrow::datatypes::DataType;
+use polars::prelude::*;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write;
+
+use crate::expectations::{ColumnExpectation, ExpectationSuite};
+use crate::loaders::dataframe::{NcodeDataFrame, NumericFeatures};
+use crate::loaders::error::Result as FrameResult;
+use crate::parsers::email::validate_email;
+use crate::parsers::iban::validate_iban;
+
+/// Outcome of a single expectation
+///
+#[derive(Debug)]
+pub struct CheckResult {
+    pub column: Option<String>,
+    pub check: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub results: Vec<CheckResult>,
+    /// Row index and the checks it failed
+    pub failing_rows: BTreeMap<usize, Vec<String>>,
+}
+
+impl ValidationReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+
+    pub fn num_failed(&self) -> usize {
+        self.results.iter().filter(|r| !r.passed).count()
+    }
+
+    fn add(&mut self, column: Option<&str>, check: &str, passed: bool, message: String) {
+        self.results.push(CheckResult {
+            column: column.map(|c| c.to_string()),
+            check: check.to_string(),
+            passed,
+            message,
+        });
+    }
+
+    /// Record a row-level check: it passes when no row violates it
+    ///
+    fn add_rows(&mut self, column: &str, check: &str, rows: Vec<usize>) {
+        let label = format!("{}:{}", column, check);
+        for row in rows.iter() {
+            self.failing_rows.entry(*row).or_insert_with(Vec::new).push(label.clone());
+        }
+        let message = if rows.is_empty() {
+            String::from("ok")
+        } else {
+            format!("{} row(s) violate {}", rows.len(), check)
+        };
+        self.add(Some(column), check, rows.is_empty(), message);
+    }
+
+    /// Write violating rows as csv, with the checks each row failed
+    ///
+    pub fn write_failing_rows<W: Write>(&self, writer: W, dataframe: &NcodeDataFrame) -> csv::Result<()> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        let mut header: Vec<String> = vec!["row".into(), "failed".into()];
+        header.extend(dataframe.dataframe.get_column_names().iter().map(|c| c.to_string()));
+        wtr.write_record(&header)?;
+
+        for (row, checks) in self.failing_rows.iter() {
+            let mut record: Vec<String> = vec![row.to_string(), checks.join(";")];
+            for series in dataframe.dataframe.get_columns() {
+                record.push(format!("{}", series.get(*row)));
+            }
+            wtr.write_record(&record)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in self.results.iter() {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+            let column = result.column.as_deref().unwrap_or("<table>");
+            writeln!(f, "[{}] {} {}: {}", status, column, result.check, result.message)?;
+        }
+        writeln!(
+            f,
+            "\n{} check(s), {} failed, {} row(s) with violations",
+            self.results.len(),
+            self.num_failed(),
+            self.failing_rows.len()
+        )
+    }
+}
+
+/// Name used in expectation files for a column type
+///
+pub fn type_name(dtype: &DataType) -> &'static str {
+    match dtype {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => "Int",
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => "Int",
+        DataType::Float32 | DataType::Float64 => "Float",
+        DataType::Utf8 | DataType::LargeUtf8 => "Str",
+        DataType::Boolean => "Bool",
+        DataType::Date32(_) | DataType::Date64(_) => "Date",
+        DataType::Timestamp(_, _) => "Timestamp",
+        _ => "Unknown",
+    }
+}
+
+/// Whether value is of semantic type, None if the type is not known
+///
+pub fn semantic_match(semantic_type: &str, value: &str) -> Option<bool> {
+    match semantic_type {
+        "Email" => Some(validate_email(value)),
+        "Iban" => Some(validate_iban(value)),
+        _ => None,
+    }
+}
+
+/// Values of a column as text, nulls included
+///
+fn text_values(series: &Series) -> FrameResult<Vec<Option<String>>> {
+    Ok(match series.dtype() {
+        DataType::Utf8 => series
+            .utf8()?
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect(),
+        DataType::Int64 | DataType::Float64 => NumericFeatures::get_values(series)?
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect(),
+        DataType::Boolean => series
+            .bool()?
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect(),
+        _ => (0..series.len()).map(|i| Some(format!("{}", series.get(i)))).collect(),
+    })
+}
+
+fn validate_column(
+    report: &mut ValidationReport,
+    series: &Series,
+    source_type: Option<&DataType>,
+    expectation: &ColumnExpectation,
+) -> FrameResult<()> {
+    let name = expectation.name.as_str();
+    let nrows = series.len();
+
+    if let Some(expected) = &expectation.dtype {
+        // the type of the source, as in profiles, not the one the column is read as
+        let actual = type_name(source_type.unwrap_or(series.dtype()));
+        report.add(Some(name), "type", actual == expected, format!("expected {}, found {}", expected, actual));
+    }
+
+    if let Some(max_ratio) = expectation.max_null_ratio {
+        let ratio = if nrows > 0 { series.null_count() as f64 / nrows as f64 } else { 0.0 };
+        report.add(
+            Some(name),
+            "max_null_ratio",
+            ratio <= max_ratio,
+            format!("null ratio {:.4} (max {})", ratio, max_ratio),
+        );
+    }
+
+    let values = text_values(series)?;
+
+    if expectation.not_null {
+        let rows = values.iter().enumerate().filter(|(_, v)| v.is_none()).map(|(i, _)| i).collect();
+        report.add_rows(name, "not_null", rows);
+    }
+
+    if expectation.unique {
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let mut rows: Vec<usize> = vec![];
+        for (i, value) in values.iter().enumerate() {
+            if let Some(v) = value {
+                let count = seen.entry(v.as_str()).or_insert(0);
+                *count += 1;
+                if *count > 1 {
+                    rows.push(i);
+                }
+            }
+        }
+        report.add_rows(name, "unique", rows);
+    }
+
+    if expectation.min.is_some() || expectation.max.is_some() {
+        let min = expectation.min.unwrap_or(f64::NEG_INFINITY);
+        let max = expectation.max.unwrap_or(f64::INFINITY);
+        let rows = values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| match v {
+                Some(v) => match v.parse::<f64>() {
+                    Ok(x) => x < min || x > max,
+                    // non numeric values cannot be in range
+                    Err(_) => true,
+                },
+                None => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+        report.add_rows(name, "range", rows);
+    }
+
+    if let Some(allowed) = &expectation.values {
+        let numeric: Vec<f64> = allowed.iter().filter_map(|a| a.parse::<f64>().ok()).collect();
+        let rows = values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| match v {
+                Some(v) => {
+                    let as_number = v.parse::<f64>().ok();
+                    !allowed.contains(v) && !as_number.map(|x| numeric.contains(&x)).unwrap_or(false)
+                }
+                None => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+        report.add_rows(name, "values", rows);
+    }
+
+    if let Some(pattern) = &expectation.regex {
+        match Regex::new(pattern) {
+            Ok(re) => {
+                let rows = values
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| match v {
+                        Some(v) => !re.is_match(v),
+                        None => false,
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                report.add_rows(name, "regex", rows);
+            }
+            Err(e) => report.add(Some(name), "regex", false, format!("invalid regex {}: {}", pattern, e)),
+        }
+    }
+
+    if let Some(semantic_type) = &expectation.semantic_type {
+        let min_ratio = expectation.min_semantic_ratio.unwrap_or(1.0);
+        let present: Vec<&String> = values.iter().filter_map(|v| v.as_ref()).collect();
+        let matches: Option<Vec<bool>> = present.iter().map(|v| semantic_match(semantic_type, v)).collect();
+        match matches {
+            Some(matches) => {
+                let nmatch = matches.iter().filter(|m| **m).count();
+                let ratio = if present.is_empty() { 1.0 } else { nmatch as f64 / present.len() as f64 };
+                report.add(
+                    Some(name),
+                    "semantic_type",
+                    ratio >= min_ratio,
+                    format!("{:.4} of values are {} (min {})", ratio, semantic_type, min_ratio),
+                );
+            }
+            None => report.add(Some(name), "semantic_type", false, format!("unknown semantic type {}", semantic_type)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Check dataframe against every expectation of suite
+///
+pub fn validate(dataframe: &NcodeDataFrame, suite: &ExpectationSuite) -> FrameResult<ValidationReport> {
+    let mut report = ValidationReport::default();
+    let nrows = dataframe.dataframe.height();
+
+    if let Some(min_rows) = suite.table.min_rows {
+        report.add(None, "min_rows", nrows >= min_rows, format!("{} rows (min {})", nrows, min_rows));
+    }
+    if let Some(max_rows) = suite.table.max_rows {
+        report.add(None, "max_rows", nrows <= max_rows, format!("{} rows (max {})", nrows, max_rows));
+    }
+
+    for expectation in suite.columns.iter() {
+        match dataframe.dataframe.column(&expectation.name) {
+            Ok(series) => {
+                report.add(Some(&expectation.name), "exists", true, String::from("ok"));
+                validate_column(&mut report, series, dataframe.source_type(&expectation.name), expectation)?;
+            }
+            Err(_) => {
+                let message = if expectation.required { "column not found" } else { "optional column not found" };
+                report.add(Some(&expectation.name), "exists", !expectation.required, String::from(message));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataframe() -> NcodeDataFrame {
+        let columns = vec![
+            Series::new("id", &[1i64, 2, 2, 4]),
+            Series::new("email", &[Some("a@x.com"), None, Some("bad"), Some("c@x.com")]),
+            Series::new("kind", &["a", "b", "c", "a"]),
+        ];
+        NcodeDataFrame::new(DataFrame::new(columns).unwrap())
+    }
+
+    fn passed(report: &ValidationReport, column: Option<&str>, check: &str) -> bool {
+        report
+            .results
+            .iter()
+            .find(|r| r.column.as_deref() == column && r.check == check)
+            .map(|r| r.passed)
+            .unwrap_or_else(|| panic!("no {} check for {:?}", check, column))
+    }
+
+    #[test]
+    fn failed_checks_and_rows() {
+        let suite = ExpectationSuite::from_toml(
+            r#"
+            [table]
+            min_rows = 5
+            max_rows = 10
+
+            [[columns]]
+            name = "id"
+            type = "Int"
+            unique = true
+            min = 1.0
+            max = 3.0
+
+            [[columns]]
+            name = "email"
+            type = "Int"
+            not_null = true
+            regex = "@"
+            max_null_ratio = 0.1
+            semantic_type = "Email"
+
+            [[columns]]
+            name = "kind"
+            type = "Str"
+            values = ["a", "b"]
+
+            [[columns]]
+            name = "missing"
+
+            [[columns]]
+            name = "optional"
+            required = false
+            "#,
+        )
+        .unwrap();
+        let dataframe = dataframe();
+        let report = validate(&dataframe, &suite).unwrap();
+
+        assert!(!passed(&report, None, "min_rows"));
+        assert!(passed(&report, None, "max_rows"));
+        assert!(passed(&report, Some("id"), "exists"));
+        assert!(passed(&report, Some("id"), "type"));
+        assert!(!passed(&report, Some("id"), "unique"));
+        assert!(!passed(&report, Some("id"), "range"));
+        assert!(!passed(&report, Some("email"), "type"));
+        assert!(!passed(&report, Some("email"), "not_null"));
+        assert!(!passed(&report, Some("email"), "regex"));
+        assert!(!passed(&report, Some("email"), "max_null_ratio"));
+        assert!(!passed(&report, Some("email"), "semantic_type"));
+        assert!(passed(&report, Some("kind"), "type"));
+        assert!(!passed(&report, Some("kind"), "values"));
+        assert!(!passed(&report, Some("missing"), "exists"));
+        assert!(passed(&report, Some("optional"), "exists"));
+        assert!(!report.passed());
+        assert_eq!(report.num_failed(), 10);
+
+        let mut content: Vec<u8> = vec![];
+        report.write_failing_rows(&mut content, &dataframe).unwrap();
+        let content = String::from_utf8(content).unwrap();
+        let rows: Vec<Vec<&str>> = content.lines().map(|l| l.splitn(3, ',').take(2).collect()).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["row", "failed"],
+                vec!["1", "email:not_null"],
+                vec!["2", "id:unique;email:regex;kind:values"],
+                vec!["3", "id:range"],
+            ]
+        );
+    }
+
+    #[test]
+    fn passing_suite() {
+        let suite = ExpectationSuite::from_toml(
+            r#"
+            [table]
+            min_rows = 1
+
+            [[columns]]
+            name = "id"
+            type = "Int"
+            not_null = true
+            min = 0.0
+            max = 10.0
+
+            [[columns]]
+            name = "kind"
+            values = ["a", "b", "c"]
+            regex = "^[a-z]$"
+            "#,
+        )
+        .unwrap();
+        let report = validate(&dataframe(), &suite).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.num_failed(), 0);
+        assert!(report.failing_rows.is_empty());
+    }
+}
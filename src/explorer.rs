@@ -0,0 +1,502 @@
+// Terminal UI to browse a profile, and the data it comes from when available
+//
+// keys: up/down (j/k) select column, / search, s change sort, tab focus preview,
+//       pgup/pgdown (p/n) page through rows, q quit
+
+use anyhow::Result;
+use crossterm::cursor::Show;
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use std::io::{stdout, Stdout};
+use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, Wrap};
+use tui::{Frame, Terminal};
+
+use xtract::loaders::dataframe::{ColumnFeatures, DataFrameMeta, NcodeDataFrame};
+use xtract::reports::{fmt_number, ColumnSummary};
+
+/// Rows shown per page in the data preview
+const PAGE_SIZE: usize = 10;
+/// Columns shown in the data preview
+const PREVIEW_COLUMNS: usize = 8;
+
+type Backend = CrosstermBackend<Stdout>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortKey {
+    /// Order of the dataset
+    Position,
+    Nulls,
+    Cardinality,
+    Type,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Position => SortKey::Nulls,
+            SortKey::Nulls => SortKey::Cardinality,
+            SortKey::Cardinality => SortKey::Type,
+            SortKey::Type => SortKey::Position,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Position => "position",
+            SortKey::Nulls => "nulls",
+            SortKey::Cardinality => "cardinality",
+            SortKey::Type => "type",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Columns,
+    Search,
+    Preview,
+}
+
+struct App {
+    profile: DataFrameMeta,
+    data: Option<NcodeDataFrame>,
+    summaries: Vec<ColumnSummary>,
+    /// Indices into summaries of the columns shown, after search and sort
+    visible: Vec<usize>,
+    search: String,
+    sort: SortKey,
+    focus: Focus,
+    list_state: ListState,
+    page: usize,
+}
+
+impl App {
+    fn new(profile: DataFrameMeta, data: Option<NcodeDataFrame>) -> Self {
+        let summaries = profile.columns().map(|(n, c)| ColumnSummary::new(n, c)).collect();
+        let mut app = App {
+            profile,
+            data,
+            summaries,
+            visible: vec![],
+            search: String::new(),
+            sort: SortKey::Position,
+            focus: Focus::Columns,
+            list_state: ListState::default(),
+            page: 0,
+        };
+        app.refresh();
+        app
+    }
+
+    /// Recompute visible columns from search and sort
+    ///
+    fn refresh(&mut self) {
+        let search = self.search.to_lowercase();
+        let mut visible: Vec<usize> = (0..self.summaries.len())
+            .filter(|i| self.summaries[*i].name.to_lowercase().contains(&search))
+            .collect();
+
+        let summaries = &self.summaries;
+        match self.sort {
+            SortKey::Position => {}
+            SortKey::Nulls => visible.sort_by(|a, b| summaries[*b].null_count.cmp(&summaries[*a].null_count)),
+            SortKey::Cardinality => visible.sort_by(|a, b| summaries[*b].nunique.cmp(&summaries[*a].nunique)),
+            SortKey::Type => visible.sort_by(|a, b| summaries[*a].dtype.cmp(&summaries[*b].dtype)),
+        }
+
+        self.visible = visible;
+        let selected = if self.visible.is_empty() { None } else { Some(0) };
+        self.list_state.select(selected);
+    }
+
+    fn selected(&self) -> Option<&ColumnSummary> {
+        self.list_state.selected().and_then(|i| self.visible.get(i)).map(|i| &self.summaries[*i])
+    }
+
+    fn move_selection(&mut self, delta: i64) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as i64;
+        let next = (current + delta).max(0).min(self.visible.len() as i64 - 1);
+        self.list_state.select(Some(next as usize));
+    }
+
+    fn num_pages(&self) -> usize {
+        match &self.data {
+            Some(data) => (data.dataframe.height() + PAGE_SIZE - 1) / PAGE_SIZE,
+            None => 0,
+        }
+    }
+
+    /// Handle a key, return false to quit
+    ///
+    fn on_key(&mut self, code: KeyCode) -> bool {
+        match self.focus {
+            Focus::Search => match code {
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.focus = Focus::Columns;
+                    self.refresh();
+                }
+                KeyCode::Enter => self.focus = Focus::Columns,
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.refresh();
+                }
+                KeyCode::Char(c) => {
+                    self.search.push(c);
+                    self.refresh();
+                }
+                _ => {}
+            },
+            _ => match code {
+                KeyCode::Char('q') | KeyCode::Esc => return false,
+                KeyCode::Char('/') => self.focus = Focus::Search,
+                KeyCode::Char('s') => {
+                    self.sort = self.sort.next();
+                    self.refresh();
+                }
+                KeyCode::Tab => {
+                    self.focus = if self.focus == Focus::Columns { Focus::Preview } else { Focus::Columns };
+                }
+                KeyCode::Up | KeyCode::Char('k') if self.focus == Focus::Columns => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') if self.focus == Focus::Columns => self.move_selection(1),
+                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Down | KeyCode::Char('j') => {
+                    if self.page + 1 < self.num_pages() {
+                        self.page += 1;
+                    }
+                }
+                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Up | KeyCode::Char('k') => {
+                    self.page = self.page.saturating_sub(1);
+                }
+                _ => {}
+            },
+        }
+        true
+    }
+}
+
+fn border_style(active: bool) -> Style {
+    if active {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    }
+}
+
+fn draw_columns(f: &mut Frame<Backend>, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .visible
+        .iter()
+        .map(|i| {
+            let s = &app.summaries[*i];
+            ListItem::new(Spans::from(vec![
+                Span::raw(s.name.clone()),
+                Span::styled(format!("  {}", s.dtype), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let title = format!(" Columns ({}/{}) sort: {} ", app.visible.len(), app.summaries.len(), app.sort.label());
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL).border_style(border_style(app.focus == Focus::Columns)))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+fn draw_detail(f: &mut Frame<Backend>, app: &App, area: Rect) {
+    let summary = match app.selected() {
+        Some(s) => s,
+        None => {
+            let empty = Paragraph::new("No column matches the search").block(Block::default().borders(Borders::ALL));
+            f.render_widget(empty, area);
+            return;
+        }
+    };
+    let column = match app.profile.column(&summary.name) {
+        Some(column) => column,
+        None => {
+            let missing = Paragraph::new(format!("Column {} is not in the profile", summary.name))
+                .block(Block::default().borders(Borders::ALL));
+            f.render_widget(missing, area);
+            return;
+        }
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(8), Constraint::Length(7)].as_ref())
+        .split(area);
+
+    let mut lines: Vec<Spans> = vec![
+        Spans::from(vec![
+            Span::styled("type ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format!("{} {}", summary.dtype, summary.semantic_type)),
+            Span::styled(if summary.categorical { "  categorical" } else { "" }, Style::default().fg(Color::Cyan)),
+        ]),
+        Spans::from(format!(
+            "nulls {} ({:.1}%)   unique {}",
+            summary.null_count,
+            100.0 * summary.null_count as f64 / column.count().max(1) as f64,
+            summary.nunique
+        )),
+    ];
+    if summary.min.is_some() {
+        lines.push(Spans::from(format!(
+            "min {}   max {}   mean {}   std {}",
+            fmt_number(summary.min),
+            fmt_number(summary.max),
+            fmt_number(summary.mean),
+            fmt_number(summary.std)
+        )));
+    }
+    if !column.types().is_empty() {
+        let types: Vec<String> = column.types().iter().map(|(t, c)| format!("{:?}: {}", t, c)).collect();
+        lines.push(Spans::from(format!("detected {}", types.join(", "))));
+    }
+    if !column.null_tokens().is_empty() {
+        let tokens: Vec<String> = column.null_tokens().iter().map(|(t, c)| format!("{:?}: {}", t, c)).collect();
+        lines.push(Spans::from(format!("null tokens {}", tokens.join(", "))));
+    }
+    if !column.top_values().is_empty() {
+        lines.push(Spans::from(Span::styled("top values", Style::default().fg(Color::DarkGray))));
+        for value in column.top_values().iter().take(8) {
+            lines.push(Spans::from(format!("  {:>8}  {}", value.count, value.value)));
+        }
+    }
+
+    let detail = Paragraph::new(lines)
+        .block(Block::default().title(format!(" {} ", summary.name)).borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, chunks[0]);
+
+    let counts: Vec<u64> = match column.features() {
+        ColumnFeatures::Numeric(numeric) => numeric.hist().map(|h| h.counts().to_vec()).unwrap_or_default(),
+        _ => column.top_values().iter().map(|v| v.count as u64).collect(),
+    };
+    let title = match column.features() {
+        ColumnFeatures::Numeric(_) => " Histogram ",
+        _ => " Top value counts ",
+    };
+    let sparkline = Sparkline::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(&counts)
+        .style(Style::default().fg(Color::Blue));
+    f.render_widget(sparkline, chunks[1]);
+}
+
+fn draw_preview(f: &mut Frame<Backend>, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style(app.focus == Focus::Preview));
+
+    let data = match &app.data {
+        Some(data) => data,
+        None => {
+            let message = Paragraph::new("No data preview: explorer was opened on a saved profile")
+                .block(block.title(" Data "));
+            f.render_widget(message, area);
+            return;
+        }
+    };
+
+    // selected column first, then the others in dataset order
+    let selected = app.selected().map(|s| s.name.clone());
+    let mut names: Vec<String> = selected.iter().cloned().collect();
+    names.extend(
+        data.dataframe
+            .get_column_names()
+            .iter()
+            .map(|n| n.to_string())
+            .filter(|n| Some(n) != selected.as_ref()),
+    );
+    names.truncate(PREVIEW_COLUMNS);
+
+    let start = app.page * PAGE_SIZE;
+    let end = (start + PAGE_SIZE).min(data.dataframe.height());
+    let rows: Vec<Row> = (start..end)
+        .map(|i| {
+            let mut cells = vec![Cell::from(i.to_string())];
+            for name in names.iter() {
+                if let Ok(series) = data.dataframe.column(name) {
+                    cells.push(Cell::from(format!("{}", series.get(i))));
+                }
+            }
+            Row::new(cells)
+        })
+        .collect();
+
+    let mut header = vec![Cell::from("row")];
+    header.extend(names.iter().map(|n| Cell::from(n.clone())));
+    let mut widths = vec![Constraint::Length(8)];
+    widths.extend(names.iter().map(|_| Constraint::Percentage((90 / names.len().max(1)) as u16)));
+
+    let title = format!(" Data (page {}/{}) ", app.page + 1, app.num_pages().max(1));
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(block.title(title))
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+fn draw(f: &mut Frame<Backend>, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(62), Constraint::Min(8), Constraint::Length(1)].as_ref())
+        .split(f.size());
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .split(rows[0]);
+
+    draw_columns(f, app, top[0]);
+    draw_detail(f, app, top[1]);
+    draw_preview(f, app, rows[1]);
+
+    let status = match app.focus {
+        Focus::Search => format!("search: {}_   (enter to keep, esc to clear)", app.search),
+        _ => format!(
+            "{}: {} rows x {} columns   / search  s sort  tab focus  n/p page  q quit",
+            app.profile.datasource(),
+            app.profile.nrows(),
+            app.profile.ncols()
+        ),
+    };
+    f.render_widget(Paragraph::new(status).style(Style::default().fg(Color::DarkGray)), rows[2]);
+}
+
+/// Raw mode and alternate screen, left when dropped: on return, error or panic
+///
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // nothing left to report errors to
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), LeaveAlternateScreen, Show);
+    }
+}
+
+/// Open the explorer until the user quits
+/// The terminal is restored even if drawing fails or panics
+///
+pub fn run(profile: DataFrameMeta, data: Option<NcodeDataFrame>) -> Result<()> {
+    let mut app = App::new(profile, data);
+
+    let _guard = TerminalGuard::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    event_loop(&mut terminal, &mut app)
+}
+
+fn event_loop(terminal: &mut Terminal<Backend>, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|f| draw(f, app))?;
+        if let Event::Key(key) = event::read()? {
+            if !app.on_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::{DataFrame, Series};
+
+    fn app() -> App {
+        let amounts: Vec<Option<f64>> = (0..12).map(|i| if i == 0 { None } else { Some(i as f64) }).collect();
+        let names: Vec<Option<&str>> = (0..12).map(|i| if i < 3 { None } else { Some("a") }).collect();
+        let dues: Vec<i64> = (0..12).collect();
+        let columns = vec![
+            Series::new("amount", &amounts),
+            Series::new("name", &names),
+            Series::new("amount_due", &dues),
+        ];
+        let data = NcodeDataFrame::new(DataFrame::new(columns).unwrap()).with_progress(false);
+        let profile = data.profile().unwrap();
+        App::new(profile, Some(data))
+    }
+
+    fn visible(app: &App) -> Vec<&str> {
+        app.visible.iter().map(|i| app.summaries[*i].name.as_str()).collect()
+    }
+
+    #[test]
+    fn search_columns() {
+        let mut app = app();
+        assert!(app.on_key(KeyCode::Char('/')));
+        for c in "AMO".chars() {
+            app.on_key(KeyCode::Char(c));
+        }
+        assert_eq!(visible(&app), vec!["amount", "amount_due"]);
+        app.on_key(KeyCode::Char('x'));
+        assert!(visible(&app).is_empty());
+        assert!(app.selected().is_none());
+        app.on_key(KeyCode::Backspace);
+        app.on_key(KeyCode::Enter);
+        assert!(app.focus == Focus::Columns);
+        assert_eq!(app.search, "AMO");
+
+        app.on_key(KeyCode::Char('/'));
+        app.on_key(KeyCode::Esc);
+        assert!(app.search.is_empty());
+        assert_eq!(visible(&app).len(), 3);
+    }
+
+    #[test]
+    fn sort_columns() {
+        let mut app = app();
+        assert_eq!(visible(&app), vec!["amount", "name", "amount_due"]);
+        app.on_key(KeyCode::Char('s'));
+        assert!(app.sort == SortKey::Nulls);
+        assert_eq!(visible(&app), vec!["name", "amount", "amount_due"]);
+        app.on_key(KeyCode::Char('s'));
+        assert_eq!(app.selected().unwrap().name, "amount");
+        for _ in 0..3 {
+            app.on_key(KeyCode::Char('s'));
+        }
+        assert!(app.sort == SortKey::Nulls);
+    }
+
+    #[test]
+    fn move_selection_and_pages() {
+        let mut app = app();
+        app.on_key(KeyCode::Up);
+        assert_eq!(app.list_state.selected(), Some(0));
+        for _ in 0..5 {
+            app.on_key(KeyCode::Char('j'));
+        }
+        assert_eq!(app.list_state.selected(), Some(2));
+
+        // with the preview focused, up and down page through rows
+        app.on_key(KeyCode::Tab);
+        assert!(app.focus == Focus::Preview);
+        assert_eq!(app.num_pages(), 2);
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::PageDown);
+        assert_eq!(app.page, 1);
+        assert_eq!(app.list_state.selected(), Some(2));
+        app.on_key(KeyCode::Char('p'));
+        app.on_key(KeyCode::Char('p'));
+        assert_eq!(app.page, 0);
+
+        assert!(!app.on_key(KeyCode::Char('q')));
+    }
+}
@@ -1,4 +1,7 @@
+pub mod analysis;
 pub mod configuration;
+pub mod expectations;
 pub mod loaders;
 pub mod parsers;
+pub mod reports;
 pub mod transformers;
\ No newline at end of file
@@ -0,0 +1,160 @@
+// Compressed inputs (gzip, zstd, bzip2, xz and zip archives), decompressed while reading
+
+use bzip2::read::BzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+    Zip,
+}
+
+impl Compression {
+    /// Compression from the extension of a path, if known
+    ///
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            "zip" => Some(Compression::Zip),
+            _ => None,
+        }
+    }
+
+    /// Compression from the first bytes of the content, None if not compressed
+    ///
+    pub fn from_magic(head: &[u8]) -> Self {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if head.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else if head.starts_with(b"PK\x03\x04") {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Compression of a local file: extension first, then magic bytes
+    ///
+    pub fn detect(path: &str) -> io::Result<Self> {
+        if let Some(compression) = Compression::from_path(path) {
+            return Ok(compression);
+        }
+        let mut head = [0u8; 6];
+        let n = File::open(path)?.read(&mut head)?;
+        Ok(Compression::from_magic(&head[..n]))
+    }
+}
+
+/// Path without the compression extension, to detect the format of the content (data.csv.gz is data.csv)
+///
+pub fn strip_extension(path: &str) -> &str {
+    match Compression::from_path(path) {
+        Some(_) => path.rsplitn(2, '.').nth(1).unwrap_or(path),
+        None => path,
+    }
+}
+
+/// Reader decompressing content on the fly
+/// Zip archives are not streams, their members are read with read_zip_member
+///
+pub fn decoder<'a, R: Read + 'a>(reader: R, compression: Compression) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Compression::Bzip2 => Box::new(BzDecoder::new(reader)),
+        Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+        Compression::Zip => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "zip archives are read one member at a time",
+            ))
+        }
+    })
+}
+
+/// Files in a zip archive, without directories and macOS metadata
+///
+pub fn zip_members<R: Read + Seek>(reader: R) -> io::Result<Vec<String>> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut members: Vec<String> = vec![];
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if !file.is_dir() && !file.name().starts_with("__MACOSX/") {
+            members.push(file.name().to_string());
+        }
+    }
+    Ok(members)
+}
+
+/// Decompressed content of one member of a zip archive
+///
+pub fn read_zip_member<R: Read + Seek>(reader: R, name: &str) -> io::Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut file = archive.by_name(name)?;
+    let mut content = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Decompress content already in memory (e.g. an object from a bucket)
+/// A zip archive must have a single member
+///
+pub fn decompress(content: Vec<u8>, compression: Compression) -> io::Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(content),
+        Compression::Zip => {
+            let members = zip_members(io::Cursor::new(&content))?;
+            match &members[..] {
+                [member] => read_zip_member(io::Cursor::new(&content), member),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected a single file in zip archive, found {:?}", members),
+                )),
+            }
+        }
+        _ => {
+            let mut decompressed = vec![];
+            decoder(&content[..], compression)?.read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn gzip_roundtrip_and_detection() {
+        let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(b"id,name\n1,a\n").unwrap();
+        let content = encoder.finish().unwrap();
+
+        assert_eq!(Compression::from_magic(&content), Compression::Gzip);
+        assert_eq!(Compression::from_path("drops/data.csv.gz"), Some(Compression::Gzip));
+        assert_eq!(strip_extension("drops/data.csv.gz"), "drops/data.csv");
+        assert_eq!(strip_extension("drops/data.csv"), "drops/data.csv");
+        assert_eq!(decompress(content, Compression::Gzip).unwrap(), b"id,name\n1,a\n".to_vec());
+    }
+}
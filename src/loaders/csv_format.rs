@@ -1,12 +1,19 @@
 use lazy_static::lazy_static;
 use regex::{Regex, RegexBuilder};
-use std::io::{Read, Seek};
+use std::io::{BufRead, BufReader, Chain, Cursor, Read};
 // use std::fmt;
 // use std::collections::HashSet;
 // use csv::ReaderBuilder;
 use std::sync::Arc;
-use arrow::csv::{ Reader, ReaderBuilder};
-use arrow::datatypes::Schema;
+use std::io::Write;
+use arrow::array::{Array, ArrayRef, BooleanArray, StringArray};
+use arrow::compute::cast;
+use arrow::csv::reader::infer_file_schema;
+use arrow::csv::{Reader, Writer};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::loaders::error::Result;
 // use arrow::record_batch::{RecordBatch, RecordBatchReader};
 // use arrow::util::pretty;
 
@@ -23,70 +30,187 @@ lazy_static! {
 }
 
 
-// #[derive(Copy)]
+/// Csv reader over any stream, seekable or not (stdin, pipes, decompressors)
+/// Only the records needed to infer the schema are buffered
+///
 pub struct CsvReader<R>
-where R: Read + Seek
+where R: Read
 {
     /// stream object
     pub reader: R,
-    /// Csv reader builder
-    pub reader_builder: ReaderBuilder,
-
+    schema: Option<SchemaRef>,
+    has_header: bool,
+    delimiter: u8,
+    batch_size: usize,
+    /// Records read to infer the schema
+    infer_records: usize,
 }
 
 impl<R> CsvReader <R>
-where R: Read + Seek
+where R: Read
 {
     pub fn new(reader: R) -> Self {
         CsvReader{
             reader,
-            reader_builder: ReaderBuilder::new()
+            schema: None,
+            has_header: true,
+            delimiter: b',',
+            batch_size: 1024,
+            infer_records: 1000,
         }
     }
 
     pub fn with_schema(mut self, schema: Arc<Schema>) -> Self {
-        self.reader_builder = self.reader_builder.with_schema(schema);
+        self.schema = Some(schema);
         self
     }
 
     pub fn with_batch_size(mut self, batch_size: usize) -> Self {
-        self.reader_builder = self.reader_builder.with_batch_size(batch_size);
+        self.batch_size = batch_size;
         self
     }
 
     pub fn with_delimiter(mut self, delimiter: u8) -> Self {
-        self.reader_builder = self.reader_builder.with_delimiter(delimiter);
+        self.delimiter = delimiter;
         self
     }
 
     pub fn has_header(mut self, has_header: bool) -> Self {
-        self.reader_builder = self.reader_builder.has_header(has_header);
+        self.has_header = has_header;
         self
     }
 
     pub fn infer_schema(mut self, nrecords: usize) -> Self {
-        self.reader_builder = self.reader_builder.infer_schema(Some(nrecords));
-        // println!("Inferred schema: {:?}", schema);
+        self.infer_records = nrecords;
         self
+    }
+
+    /// Read the head of the stream, up to the lines needed to infer the schema
+    ///
+    fn read_head(reader: &mut BufReader<R>, nlines: usize) -> Result<Vec<u8>> {
+        let mut head: Vec<u8> = vec![];
+        for _ in 0..nlines {
+            if reader.read_until(b'\n', &mut head)? == 0 {
+                break;
+            }
+        }
+        Ok(head)
+    }
 
-        // let mut rdr = self.reader_builder.from_reader(&data[..]);
-        // let headers = self.reader_builder.has_headers(true);
+    /// Schema given or inferred from the head of the stream,
+    /// and the stream with the buffered head to be read again first
+    ///
+    fn open(self) -> Result<(SchemaRef, Chain<Cursor<Vec<u8>>, BufReader<R>>)> {
+        let mut reader = BufReader::new(self.reader);
+        let (schema, head) = match self.schema {
+            Some(schema) => (schema, vec![]),
+            None => {
+                let nlines = self.infer_records + if self.has_header { 1 } else { 0 };
+                let head = Self::read_head(&mut reader, nlines)?;
+                let (schema, _) = infer_file_schema(
+                    &mut Cursor::new(&head),
+                    self.delimiter,
+                    Some(self.infer_records),
+                    self.has_header,
+                )?;
+                (Arc::new(schema), head)
+            }
+        };
+        Ok((schema, Cursor::new(head).chain(reader)))
+    }
 
-        // // infer schema
-        // let headers: Vec<String> = headers.iter().map(|s| s.to_string()).collect();
-        // let header_length = headers.len();
-        // println!("headers: {:?}\nlen: {}", headers, header_length);
+    /// Reader of record batches, the buffered head is read again before the rest of the stream
+    ///
+    pub fn finish(self) -> Result<Reader<Chain<Cursor<Vec<u8>>, BufReader<R>>>> {
+        let (has_header, delimiter, batch_size) = (self.has_header, self.delimiter, self.batch_size);
+        let (schema, reader) = self.open()?;
+        Ok(Reader::new(reader, schema, has_header, Some(delimiter), batch_size, None, None))
     }
 
-    pub fn finish(mut self) -> Reader<R> {
-        self.reader_builder.build(self.reader).unwrap()
+    /// Read every record batch, typing columns as inferred from the head of the stream
+    /// A column with a value further down that does not parse as its inferred type stays Utf8
+    /// instead of failing the whole read (the stream cannot be read again)
+    ///
+    pub fn read(self) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let (has_header, delimiter, batch_size) = (self.has_header, self.delimiter, self.batch_size);
+        let (inferred, reader) = self.open()?;
+        let text = Arc::new(Schema::new(
+            inferred
+                .fields()
+                .iter()
+                .map(|f| Field::new(f.name(), DataType::Utf8, f.is_nullable()))
+                .collect(),
+        ));
+        let batches = Reader::new(reader, text, has_header, Some(delimiter), batch_size, None, None)
+            .collect::<std::result::Result<Vec<RecordBatch>, _>>()?;
+
+        let mut columns: Vec<Vec<ArrayRef>> = vec![vec![]; batches.len()];
+        let mut fields: Vec<Field> = vec![];
+        for (i, field) in inferred.fields().iter().enumerate() {
+            let typed = batches
+                .iter()
+                .map(|batch| parse_column(batch.column(i), field.data_type()))
+                .collect::<Option<Vec<ArrayRef>>>();
+            let (field, chunks) = match typed {
+                Some(chunks) => (field.clone(), chunks),
+                None => {
+                    log::warn!("Column {} is read as text, not all its values are {:?}", field.name(), field.data_type());
+                    let chunks = batches.iter().map(|batch| batch.column(i).clone()).collect();
+                    (Field::new(field.name(), DataType::Utf8, field.is_nullable()), chunks)
+                }
+            };
+            for (batch, chunk) in columns.iter_mut().zip(chunks) {
+                batch.push(chunk);
+            }
+            fields.push(field);
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let batches = columns
+            .into_iter()
+            .map(|batch| RecordBatch::try_new(schema.clone(), batch))
+            .collect::<std::result::Result<Vec<RecordBatch>, _>>()?;
+        Ok((schema, batches))
     }
+}
 
+/// Text column parsed as the given type, None if one of its values does not parse
+/// Empty values are nulls, as when reading typed columns
+///
+fn parse_column(text: &ArrayRef, to: &DataType) -> Option<ArrayRef> {
+    let values = text.as_any().downcast_ref::<StringArray>()?;
+    let missing = (0..values.len()).filter(|&i| values.is_null(i) || values.value(i).is_empty()).count();
+    let parsed: ArrayRef = match to {
+        DataType::Utf8 => return Some(text.clone()),
+        DataType::Boolean => Arc::new(BooleanArray::from(
+            (0..values.len())
+                .map(|i| match values.value(i) {
+                    _ if values.is_null(i) => None,
+                    v if v.eq_ignore_ascii_case("true") => Some(true),
+                    v if v.eq_ignore_ascii_case("false") => Some(false),
+                    _ => None,
+                })
+                .collect::<Vec<Option<bool>>>(),
+        )),
+        // values that do not parse are cast to nulls
+        _ => cast(text, to).ok()?,
+    };
+    if parsed.null_count() == missing {
+        Some(parsed)
+    } else {
+        None
+    }
+}
 
-    // pub fn get_column(mut self, colname: String) {
-    //     let some = self.reader_builder.build(reader: R)
 
-    // }
+/// Write record batches as csv with a header line
+///
+pub fn write_csv<W: Write>(writer: W, batches: &[RecordBatch]) -> Result<()> {
+    let mut writer = Writer::new(writer);
+    for batch in batches.iter() {
+        writer.write(batch)?;
+    }
+    Ok(())
 }
 
 
@@ -152,4 +276,31 @@ where R: Read + Seek
 
 //     // Ok()
 
-// }
\ No newline at end of file
+// }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stream that can only be read forward, like stdin or a pipe
+    struct Pipe<'a>(&'a [u8]);
+
+    impl Read for Pipe<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn read_stream_with_values_not_inferred() {
+        let data = b"id,amount,active\n1,2.5,true\n2,,false\n3,n/a,TRUE\n4,1.0,\n";
+        let (schema, batches) = CsvReader::new(Pipe(data)).infer_schema(2).with_batch_size(2).read().unwrap();
+
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(schema.field(2).data_type(), &DataType::Boolean);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 4);
+        assert_eq!(batches[1].column(2).null_count(), 1);
+    }
+}
@@ -1,22 +1,38 @@
-use arrow::datatypes::DataType;
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
 use histo_fp::Histogram;
 use indicatif::{ProgressBar, ProgressStyle};
 use polars::prelude::*;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::hash::Hasher;
 use std::sync::Arc;
 // use rayon::prelude::*;
 
+use crate::analysis::outliers::{self, Outlier, OutlierConfig, OutlierCounts};
+use crate::loaders::error::{DataFrameError, Result as FrameResult};
+use crate::loaders::frame;
+use crate::loaders::json_format::{json_kind, JsonSchema};
+use crate::loaders::nulls::{NullTokenCounts, NullTokens};
+use crate::loaders::source::SourceMeta;
+use crate::parsers::email::validate_email;
 use crate::parsers::iban::validate_iban;
 
+/// Number of most frequent values kept in the profile of a column
+const MAX_TOP_VALUES: usize = 20;
+
 /// Struct for JSON serialization
 ///
 #[derive(Serialize, Deserialize)]
 pub struct DataFrameMeta {
     datasource: String,
+    /// Where the data was read from (url, size, etag, last modified)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<SourceMeta>,
     hash: String,
     profile: ProfileMeta,
 }
@@ -37,6 +53,38 @@ impl DataFrameMeta {
     pub fn data_id(&self) -> String {
         self.profile.data_id.to_owned()
     }
+
+    /// Return metadata of column with name
+    ///
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.profile.columns.get(name)
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.profile.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.profile.ncols
+    }
+
+    pub fn datasource(&self) -> &str {
+        &self.datasource
+    }
+
+    pub fn source(&self) -> Option<&SourceMeta> {
+        self.source.as_ref()
+    }
+
+    /// Return (name, metadata) of each column in the order of the dataframe
+    ///
+    pub fn columns(&self) -> impl Iterator<Item = (&String, &Column)> {
+        self.profile.columns.iter()
+    }
+
+    pub fn correlations(&self) -> Option<&Correlations> {
+        self.profile.correlations.as_ref()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -44,7 +92,51 @@ pub struct ProfileMeta {
     data_id: String,
     nrows: usize,
     ncols: usize,
-    columns: HashMap<String, Column>,
+    /// Columns in the order of the dataframe
+    columns: IndexMap<String, Column>,
+    #[serde(default)]
+    correlations: Option<Correlations>,
+}
+
+/// Pearson correlation between each pair of numeric columns
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Correlations {
+    pub columns: Vec<String>,
+    /// Row major matrix, values[i][j] is the correlation of columns[i] and columns[j],
+    /// None (null in json) when undefined, e.g. for a constant column
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+/// Pearson correlation over the rows where both values are present
+/// None with less than two such rows or when one of the columns is constant over them
+///
+fn pearson(x: &[Option<f64>], y: &[Option<f64>]) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = x
+        .iter()
+        .zip(y.iter())
+        .filter_map(|(a, b)| match (a, b) {
+            (Some(a), Some(b)) => Some((*a, *b)),
+            _ => None,
+        })
+        .collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (a, b) in pairs.iter() {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -55,10 +147,38 @@ pub struct Column {
     null_count: usize,
     categorical: bool,
     features: ColumnFeatures,
-    types: HashMap<ColumnType, usize>,
+    types: BTreeMap<ColumnType, usize>,
+    #[serde(default)]
+    null_tokens: NullTokenCounts,
+    #[serde(default)]
+    dtype: Option<DataType>,
+    /// Most frequent values, in decreasing order of count
+    #[serde(default)]
+    top_values: Vec<ValueCount>,
+    /// Records having the field, for sources without a fixed schema (json)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    present: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Keep the n most frequent values (ties sorted by value)
+///
+fn top_values(value_counts: HashMap<String, usize>, n: usize) -> Vec<ValueCount> {
+    let mut values: Vec<ValueCount> = value_counts
+        .into_iter()
+        .map(|(value, count)| ValueCount { value, count })
+        .collect();
+    values.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+    values.truncate(n);
+    values
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ColumnType {
     Str,
     Float,
@@ -77,6 +197,68 @@ pub enum ColumnType {
 pub enum ColumnFeatures {
     Numeric(NumericFeatures),
     String(StringFeatures),
+    Boolean(BooleanFeatures),
+    List(ListFeatures),
+}
+
+/// Lists stored as json text, e.g. arrays of a json input
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListFeatures {
+    min_len: usize,
+    max_len: usize,
+    mean_len: f64,
+    /// Distribution of lengths
+    lengths: Hist,
+    /// Kinds of elements (int, string, object, ...) with counts
+    element_types: BTreeMap<String, usize>,
+}
+
+impl ListFeatures {
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    pub fn mean_len(&self) -> f64 {
+        self.mean_len
+    }
+
+    pub fn lengths(&self) -> &Hist {
+        &self.lengths
+    }
+
+    pub fn element_types(&self) -> &BTreeMap<String, usize> {
+        &self.element_types
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BooleanFeatures {
+    true_count: usize,
+    false_count: usize,
+}
+
+impl BooleanFeatures {
+    pub fn true_count(&self) -> usize {
+        self.true_count
+    }
+
+    pub fn false_count(&self) -> usize {
+        self.false_count
+    }
+}
+
+/// Min and max known in advance from the source (e.g. parquet statistics)
+/// and used instead of scanning the column
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExactStatistics {
+    pub min: f64,
+    pub max: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -97,25 +279,41 @@ impl Hist {
         self.bins = bins;
         self.counts = counts;
     }
+
+    /// Start of each bin
+    pub fn bins(&self) -> &[f64] {
+        &self.bins
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NumericFeatures {
-    min: f64,
-    max: f64,
-    mean: f64,
-    variance: f64,
-    std: f64,
+    /// None (null in json) when the column has no values
+    min: Option<f64>,
+    max: Option<f64>,
+    mean: Option<f64>,
+    /// None with less than two values
+    variance: Option<f64>,
+    std: Option<f64>,
     hist: Option<Hist>,
+    #[serde(default)]
+    outliers: Option<OutlierCounts>,
 }
 
 impl NumericFeatures {
+    /// Statistics of a numeric column, None when they are undefined (e.g. no values)
+    ///
     fn get_numeric_features(data: &Series) -> Self {
-        let max: f64 = data.max().unwrap();
-        let min: f64 = data.min().unwrap();
-        let mean: f64 = data.mean().unwrap();
-        let std: f64 = data.std_as_series().sum().unwrap();
-        let variance: f64 = data.var_as_series().sum().unwrap();
+        let defined = |v: Option<f64>| v.filter(|v| v.is_finite());
+        let max = defined(data.max());
+        let min = defined(data.min());
+        let mean = defined(data.mean());
+        let std = defined(data.std_as_series().sum());
+        let variance = defined(data.var_as_series().sum());
 
         Self {
             min,
@@ -124,8 +322,39 @@ impl NumericFeatures {
             variance,
             std,
             hist: Some(Hist::new()),
+            outliers: None,
         }
     }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.mean
+    }
+
+    pub fn std(&self) -> Option<f64> {
+        self.std
+    }
+
+    pub fn hist(&self) -> Option<&Hist> {
+        self.hist.as_ref()
+    }
+
+    pub fn outliers(&self) -> Option<&OutlierCounts> {
+        self.outliers.as_ref()
+    }
+
+    /// Values of a numeric column as f64, nulls included
+    ///
+    pub(crate) fn get_values(data: &Series) -> FrameResult<Vec<Option<f64>>> {
+        Ok(data.cast::<Float64Type>()?.f64()?.into_iter().collect())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -153,6 +382,12 @@ impl StringFeatures {
     }
 }
 
+/// Few distinct values compared to the rows, never for a column without rows
+///
+fn is_categorical(nunique: usize, count: usize, threshold: f64) -> bool {
+    count > 0 && (nunique as f64 / count as f64) < threshold
+}
+
 impl Column {
     /// Create new metadata for column
     pub fn new(
@@ -161,10 +396,8 @@ impl Column {
         count: usize,
         null_count: usize,
         features: ColumnFeatures,
-        types: HashMap<ColumnType, usize>,
+        types: BTreeMap<ColumnType, usize>,
     ) -> Self {
-        assert!(count > 0);
-        let ratio = nunique as f64 / count as f64;
         const THRESHOLD: f64 = 0.2;
 
         Column {
@@ -173,48 +406,349 @@ impl Column {
             nunique,
             count,
             null_count,
-            categorical: ratio < THRESHOLD,
+            categorical: is_categorical(nunique, count, THRESHOLD),
             features,
             types,
+            null_tokens: BTreeMap::new(),
+            dtype: None,
+            top_values: vec![],
+            present: None,
         }
     }
 
     /// Set categorical flag wrt to threshold
     ///
     pub fn set_categorical(&mut self, threshold: f64) {
-        assert!(self.count > 0);
-
-        let ratio = self.nunique as f64 / self.count as f64;
-        self.categorical = ratio < threshold;
+        self.categorical = is_categorical(self.nunique, self.count, threshold);
     }
 
     pub fn set_hash(&mut self, hash: String) {
         self.hash = hash;
     }
+
+    pub fn set_dtype(&mut self, dtype: DataType) {
+        self.dtype = Some(dtype);
+    }
+
+    pub fn set_top_values(&mut self, top_values: Vec<ValueCount>) {
+        self.top_values = top_values;
+    }
+
+    pub fn set_present(&mut self, present: usize) {
+        self.present = Some(present);
+    }
+
+    pub fn is_categorical(&self) -> bool {
+        self.categorical
+    }
+
+    pub fn features(&self) -> &ColumnFeatures {
+        &self.features
+    }
+
+    pub fn nunique(&self) -> usize {
+        self.nunique
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    pub fn dtype(&self) -> Option<&DataType> {
+        self.dtype.as_ref()
+    }
+
+    pub fn types(&self) -> &BTreeMap<ColumnType, usize> {
+        &self.types
+    }
+
+    pub fn top_values(&self) -> &[ValueCount] {
+        &self.top_values
+    }
+
+    pub fn null_tokens(&self) -> &NullTokenCounts {
+        &self.null_tokens
+    }
+
+    pub fn present(&self) -> Option<usize> {
+        self.present
+    }
+
+    /// Set occurrences of the null tokens found in this column
+    ///
+    pub fn set_null_tokens(&mut self, null_tokens: NullTokenCounts) {
+        self.null_tokens = null_tokens;
+    }
 }
 
 pub struct NcodeDataFrame {
     pub dataframe: Arc<DataFrame>,
+    /// Null tokens replaced in each column before profiling
+    null_tokens: HashMap<String, NullTokenCounts>,
+    outlier_config: OutlierConfig,
+    /// Draw a progress bar per column while profiling
+    progress: bool,
+    statistics: HashMap<String, ExactStatistics>,
+    /// Text columns holding json lists
+    list_columns: HashSet<String>,
+    /// Records having each column, when not all of them do
+    presence: HashMap<String, usize>,
+    /// Arrow type of the columns read as another type (e.g. dates read as Int64)
+    source_types: HashMap<String, DataType>,
+    source: Option<SourceMeta>,
 }
 
 impl NcodeDataFrame {
-    pub fn profile(&self) -> DataFrameMeta {
+    pub fn new(dataframe: DataFrame) -> Self {
+        NcodeDataFrame {
+            dataframe: Arc::new(dataframe),
+            null_tokens: HashMap::new(),
+            outlier_config: OutlierConfig::default(),
+            progress: true,
+            statistics: HashMap::new(),
+            list_columns: HashSet::new(),
+            presence: HashMap::new(),
+            source_types: HashMap::new(),
+            source: None,
+        }
+    }
+
+    /// Dataframe over the columns of an arrow dataframe (e.g. read from parquet), sharing their chunks
+    /// Columns of types that are not profiled (e.g. decimals, lists) are skipped
+    ///
+    pub fn from_frame(frame: &frame::DataFrame) -> FrameResult<Self> {
+        let mut columns: Vec<Series> = vec![];
+        let mut source_types: HashMap<String, DataType> = HashMap::new();
+        for i in 0..frame.num_columns() {
+            let column = frame.column(i);
+            if let Some(series) = column_to_series(column)? {
+                let source_type = match column.data_type() {
+                    DataType::Dictionary(_, values) => values.as_ref(),
+                    other => other,
+                };
+                if source_type != series.dtype() {
+                    source_types.insert(series.name().to_string(), source_type.clone());
+                }
+                columns.push(series);
+            }
+        }
+        let dataframe = DataFrame::new(columns).map_err(|e| DataFrameError::ComputeError(e.to_string()))?;
+        let mut dataframe = NcodeDataFrame::new(dataframe);
+        dataframe.source_types = source_types;
+        Ok(dataframe)
+    }
+
+    /// Arrow type column was read from, when it is profiled as another type
+    ///
+    pub fn source_type(&self, column: &str) -> Option<&DataType> {
+        self.source_types.get(column)
+    }
+
+    /// Dates and timestamps, profiled by their range only
+    ///
+    fn is_temporal(&self, column: &str) -> bool {
+        self.source_type(column).map_or(false, is_temporal)
+    }
+
+    /// Dataframe over arrow record batches, each batch a chunk of the columns
+    ///
+    pub fn from_record_batches(schema: Arc<Schema>, batches: Vec<RecordBatch>) -> FrameResult<Self> {
+        if batches.is_empty() {
+            return Err(DataFrameError::NoneError);
+        }
+        NcodeDataFrame::from_frame(&frame::DataFrame::from_record_batches(schema, batches)?)
+    }
+
+    /// Profile list columns and record field presence found reading json
+    ///
+    pub fn with_json_schema(mut self, schema: &JsonSchema) -> Self {
+        self.list_columns = schema.list_columns();
+        self.presence = schema.presence();
+        self
+    }
+
+    /// Rows of all frames one after the other, e.g. the partitions of a dataset
+    /// Frames must have the same columns and types
+    ///
+    pub fn concat(frames: Vec<NcodeDataFrame>) -> FrameResult<Self> {
+        let mut frames = frames.into_iter();
+        let mut combined = frames.next().ok_or(DataFrameError::NoneError)?;
+        for frame in frames {
+            let stacked = combined
+                .dataframe
+                .vstack(&frame.dataframe)
+                .map_err(|e| DataFrameError::ComputeError(format!("Partitions do not have the same schema: {}", e)))?;
+            combined.dataframe = Arc::new(stacked);
+            // exact min and max only for columns known in every partition
+            combined.statistics.retain(|column, known| match frame.statistics.get(column) {
+                Some(stats) => {
+                    known.min = known.min.min(stats.min);
+                    known.max = known.max.max(stats.max);
+                    true
+                }
+                None => false,
+            });
+        }
+        Ok(combined)
+    }
+
+    /// Keep only these columns, in this order
+    ///
+    pub fn select(mut self, columns: &[String]) -> FrameResult<Self> {
+        let selected = self
+            .dataframe
+            .select(columns.iter().map(|c| c.as_str()).collect::<Vec<&str>>())
+            .map_err(|e| DataFrameError::ComputeError(e.to_string()))?;
+        self.dataframe = Arc::new(selected);
+        Ok(self)
+    }
+
+    /// Arrow dataframe over the (possibly normalized) columns, sharing the chunks of each series
+    ///
+    pub fn to_frame(&self) -> FrameResult<frame::DataFrame> {
+        let columns = self
+            .dataframe
+            .get_columns()
+            .iter()
+            .map(|series| {
+                let field = Field::new(series.name(), series.dtype().clone(), true);
+                frame::Column::from_arrays(series.chunks().clone(), field)
+            })
+            .collect::<FrameResult<Vec<frame::Column>>>()?;
+        Ok(frame::DataFrame::from_columns(columns))
+    }
+
+    /// Use min and max known from the source for these columns
+    ///
+    pub fn with_statistics(mut self, statistics: HashMap<String, ExactStatistics>) -> Self {
+        self.statistics = statistics;
+        self
+    }
+
+    /// Min and max known from the source, unless null tokens were replaced in the column:
+    /// the source statistics still count them as values
+    ///
+    fn exact_statistics(&self, column: &str) -> Option<&ExactStatistics> {
+        match self.null_tokens.get(column) {
+            Some(counts) if !counts.is_empty() => None,
+            _ => self.statistics.get(column),
+        }
+    }
+
+    /// Record where the data was read from in the profile
+    ///
+    pub fn with_source(mut self, source: SourceMeta) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn source(&self) -> Option<&SourceMeta> {
+        self.source.as_ref()
+    }
+
+    /// Show or hide progress bars (e.g. hidden when stderr is not a terminal)
+    ///
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Set thresholds used to detect outliers in numeric columns
+    ///
+    pub fn with_outlier_config(mut self, config: OutlierConfig) -> Self {
+        self.outlier_config = config;
+        self
+    }
+
+    /// Replace null tokens with nulls in every column
+    /// Text columns that turn out to be numeric once tokens are removed are re-typed
+    ///
+    pub fn with_null_tokens(mut self, tokens: &NullTokens) -> FrameResult<Self> {
+        let mut columns: Vec<Series> = vec![];
+        let mut null_tokens: HashMap<String, NullTokenCounts> = HashMap::new();
+
+        for series in self.dataframe.get_columns() {
+            let (normalized, counts) = tokens.normalize_series(series)?;
+            if normalized.dtype() != series.dtype() {
+                self.source_types.remove(series.name());
+            }
+            if !counts.is_empty() {
+                null_tokens.insert(series.name().to_string(), counts);
+            }
+            columns.push(normalized);
+        }
+
+        let dataframe = DataFrame::new(columns)?;
+        self.dataframe = Arc::new(dataframe);
+        self.null_tokens = null_tokens;
+        Ok(self)
+    }
+
+    /// Rows of numeric columns flagged as outliers by at least one method
+    ///
+    pub fn outliers(&self) -> FrameResult<Vec<(String, Outlier)>> {
+        let mut result: Vec<(String, Outlier)> = vec![];
+
+        for series in self.dataframe.get_columns() {
+            match series.dtype() {
+                DataType::Int64 | DataType::Float64 if !self.is_temporal(series.name()) => {
+                    let values = NumericFeatures::get_values(series)?;
+                    let (_counts, found) = outliers::detect(&values, &self.outlier_config);
+                    result.extend(found.into_iter().map(|o| (series.name().to_string(), o)));
+                }
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    /// Correlation matrix of numeric columns, None if there are less than two
+    ///
+    pub fn correlations(&self) -> FrameResult<Option<Correlations>> {
+        let numeric: Vec<(String, Vec<Option<f64>>)> = self
+            .dataframe
+            .get_columns()
+            .iter()
+            .filter(|s| matches!(s.dtype(), DataType::Int64 | DataType::Float64) && !self.is_temporal(s.name()))
+            .map(|s| Ok((s.name().to_string(), NumericFeatures::get_values(s)?)))
+            .collect::<FrameResult<_>>()?;
+        if numeric.len() < 2 {
+            return Ok(None);
+        }
+
+        let values = numeric
+            .iter()
+            .map(|(_, x)| numeric.iter().map(|(_, y)| pearson(x, y)).collect())
+            .collect();
+
+        Ok(Some(Correlations {
+            columns: numeric.into_iter().map(|(name, _)| name).collect(),
+            values,
+        }))
+    }
+
+    pub fn profile(&self) -> FrameResult<DataFrameMeta> {
         let (nrows, ncols) = self.dataframe.shape();
         let colnames = self.dataframe.get_column_names();
         let mut coltypes: Vec<&DataType> = vec![];
         // meta data for single column
-        let mut columns_meta: HashMap<String, Column> = HashMap::new();
+        let mut columns_meta: IndexMap<String, Column> = IndexMap::new();
 
         for (_i, colname) in colnames.iter().enumerate() {
             // extract values of this column
-            let colvalues = self.dataframe.column(colname).unwrap();
+            let colvalues = self.dataframe.column(colname)?;
             // extract inferred column type
             let coltype = colvalues.dtype();
             coltypes.push(coltype);
             let mut hasher = DefaultHasher::new();
-            let mut parsed_types: HashMap<ColumnType, usize> = HashMap::new();
+            let mut parsed_types: BTreeMap<ColumnType, usize> = BTreeMap::new();
+            let mut value_counts: HashMap<String, usize> = HashMap::new();
             let colfeats: ColumnFeatures;
-            let pb = ProgressBar::new(nrows as u64);
+            let pb = if self.progress { ProgressBar::new(nrows as u64) } else { ProgressBar::hidden() };
             let prefix = format!("Column: {}\t\t", colname);
             let s = ("Fade in: ", "█▉▊▋▌▍▎▏  ", "yellow");
             pb.set_style(
@@ -231,13 +765,13 @@ impl NcodeDataFrame {
                     let mut histogram = Histogram::with_buckets(10, None);
                     let mut j = 0;
                     colvalues
-                        .i64()
-                        .expect("Something wrong happened reading column")
+                        .i64()?
                         .into_iter()
                         .for_each(|element| {
                             match element {
                                 Some(el) => {
                                     histogram.add(el as f64);
+                                    *value_counts.entry(el.to_string()).or_insert(0) += 1;
                                     let num_str = el.to_ne_bytes();
                                     hasher.write(&num_str);
                                     // update progress bar
@@ -246,7 +780,8 @@ impl NcodeDataFrame {
                                     j += 1;
                                 }
 
-                                _ => panic!("Something wrong happened during conversion"),
+                                // nulls are counted separately
+                                None => pb.inc(1),
                             }
                         });
 
@@ -261,6 +796,21 @@ impl NcodeDataFrame {
                     let hist = Hist { bins, counts };
                     let mut numeric_features = NumericFeatures::get_numeric_features(&colvalues);
                     numeric_features.hist = Some(hist);
+                    if let Some(stats) = self.exact_statistics(colname) {
+                        numeric_features.min = Some(stats.min);
+                        numeric_features.max = Some(stats.max);
+                    }
+                    if self.is_temporal(colname) {
+                        // days or time units since the epoch (see dtype): a range, no arithmetic
+                        numeric_features.mean = None;
+                        numeric_features.variance = None;
+                        numeric_features.std = None;
+                        numeric_features.hist = None;
+                    } else {
+                        let values = NumericFeatures::get_values(&colvalues)?;
+                        let (outlier_counts, _) = outliers::detect(&values, &self.outlier_config);
+                        numeric_features.outliers = Some(outlier_counts);
+                    }
                     // colfeats = ColumnFeatures::Numeric{features: numeric_features};
                     colfeats = ColumnFeatures::Numeric(numeric_features);
                 }
@@ -269,14 +819,14 @@ impl NcodeDataFrame {
                     let mut histogram = Histogram::with_buckets(10, None);
                     let mut j = 0;
                     colvalues
-                        .f64()
-                        .expect("Something wrong happened reading column")
+                        .f64()?
                         .into_iter()
                         .for_each(|element| {
                             match element {
                                 Some(el) => {
                                     // count into histogram
                                     histogram.add(el);
+                                    *value_counts.entry(el.to_string()).or_insert(0) += 1;
                                     let num_str = el.to_ne_bytes();
                                     hasher.write(&num_str);
                                     // update progress bar
@@ -285,7 +835,8 @@ impl NcodeDataFrame {
                                     j += 1;
                                 }
 
-                                _ => panic!("Something wrong happened during conversion"),
+                                // nulls are counted separately
+                                None => pb.inc(1),
                             }
                         });
 
@@ -298,15 +849,59 @@ impl NcodeDataFrame {
                     let hist = Hist { bins, counts };
                     let mut numeric_features = NumericFeatures::get_numeric_features(&colvalues);
                     numeric_features.hist = Some(hist);
+                    if let Some(stats) = self.exact_statistics(colname) {
+                        numeric_features.min = Some(stats.min);
+                        numeric_features.max = Some(stats.max);
+                    }
+                    let values = NumericFeatures::get_values(&colvalues)?;
+                    let (outlier_counts, _) = outliers::detect(&values, &self.outlier_config);
+                    numeric_features.outliers = Some(outlier_counts);
                     // colfeats = ColumnFeatures::Numeric{features: numeric_features};
                     colfeats = ColumnFeatures::Numeric(numeric_features);
                 }
 
-                DataType::Utf8 => {
-                    // if inferred type is string, try parse each element into known regex
-                    let regex_email_address =
-                        Regex::new(r"(^[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+$)").unwrap();
+                DataType::Utf8 if self.list_columns.contains(*colname) => {
+                    let mut histogram = Histogram::with_buckets(10, None);
+                    let mut lengths: Vec<usize> = vec![];
+                    let mut element_types: BTreeMap<String, usize> = BTreeMap::new();
+                    colvalues
+                        .utf8()?
+                        .into_iter()
+                        .for_each(|element| {
+                            if let Some(el) = element {
+                                *value_counts.entry(el.to_string()).or_insert(0) += 1;
+                                hasher.write(el.as_bytes());
+                                // values that are not lists count as lists of one element
+                                let elements = match serde_json::from_str::<serde_json::Value>(el) {
+                                    Ok(serde_json::Value::Array(elements)) => elements,
+                                    Ok(value) => vec![value],
+                                    Err(_) => vec![serde_json::Value::String(el.to_string())],
+                                };
+                                for e in elements.iter() {
+                                    *element_types.entry(json_kind(e).to_string()).or_insert(0) += 1;
+                                }
+                                histogram.add(elements.len() as f64);
+                                lengths.push(elements.len());
+                            }
+                            pb.inc(1);
+                        });
+
+                    let mut hist = Hist::new();
+                    hist.update(
+                        histogram.buckets().map(|b| b.start()).collect(),
+                        histogram.buckets().map(|b| b.count()).collect(),
+                    );
+                    colfeats = ColumnFeatures::List(ListFeatures {
+                        min_len: lengths.iter().copied().min().unwrap_or(0),
+                        max_len: lengths.iter().copied().max().unwrap_or(0),
+                        mean_len: lengths.iter().sum::<usize>() as f64 / lengths.len().max(1) as f64,
+                        lengths: hist,
+                        element_types,
+                    });
+                }
 
+                DataType::Utf8 => {
+                    // if inferred type is string, try parse each element into known types
                     // TODO
                     // get_string_features(&colvalues) and move parsing into get_string_features
                     let string_features = StringFeatures::get_string_features(&colvalues);
@@ -316,13 +911,13 @@ impl NcodeDataFrame {
                     let mut total_len: usize = 0;
                     let mut j = 0;
                     colvalues
-                        .utf8()
-                        .expect("Something wrong happened reading column")
+                        .utf8()?
                         .into_iter()
                         .for_each(|element| {
                             match element {
                                 Some(el) => {
-                                    let is_email = regex_email_address.is_match(el);
+                                    *value_counts.entry(el.to_string()).or_insert(0) += 1;
+                                    let is_email = validate_email(el);
                                     let is_iban = validate_iban(el);
                                     if is_email {
                                         *parsed_types.entry(ColumnType::Email).or_insert(0) += 1;
@@ -343,22 +938,56 @@ impl NcodeDataFrame {
                                     j += 1;
                                 }
 
-                                _ => panic!("Something wrong happened during conversion"),
+                                // nulls are counted separately
+                                None => pb.inc(1),
                             }
                         });
 
                     let _mean_element_len = total_len as f64 / nrows as f64;
                 }
 
-                _ => unimplemented!(),
+                DataType::Boolean => {
+                    let mut features = BooleanFeatures { true_count: 0, false_count: 0 };
+                    colvalues
+                        .bool()?
+                        .into_iter()
+                        .for_each(|element| {
+                            if let Some(el) = element {
+                                if el {
+                                    features.true_count += 1;
+                                } else {
+                                    features.false_count += 1;
+                                }
+                                *value_counts.entry(el.to_string()).or_insert(0) += 1;
+                                hasher.write(&[el as u8]);
+                            }
+                            pb.inc(1);
+                        });
+                    colfeats = ColumnFeatures::Boolean(features);
+                }
+
+                other => {
+                    return Err(DataFrameError::ComputeError(format!(
+                        "Cannot profile column {} of type {:?}",
+                        colname, other
+                    )))
+                }
             }
 
             let colhash = hasher.finish().to_string();
             let null_count = colvalues.null_count();
             // get number of unique values
-            let nunique = colvalues.unique().unwrap().len();
+            let nunique = colvalues.unique()?.len();
 
-            let col = Column::new(colhash, nunique, nrows, null_count, colfeats, parsed_types);
+            let mut col = Column::new(colhash, nunique, nrows, null_count, colfeats, parsed_types);
+            if let Some(counts) = self.null_tokens.get(*colname) {
+                col.set_null_tokens(counts.clone());
+            }
+            col.set_dtype(self.source_type(colname).unwrap_or(coltype).clone());
+            col.set_top_values(top_values(value_counts, MAX_TOP_VALUES));
+            if let Some(present) = self.presence.get(*colname) {
+                col.set_present(*present);
+            }
 
             columns_meta.insert(colname.to_string(), col);
             pb.finish_with_message("done");
@@ -369,15 +998,212 @@ impl NcodeDataFrame {
             nrows,
             ncols,
             columns: columns_meta,
+            correlations: self.correlations()?,
         };
 
         let dfmeta = DataFrameMeta {
             datasource: String::from(""),
+            source: self.source.clone(),
             // TODO hash of all sorted columns hashes
             hash: String::from("TODO"),
             profile: profilemeta,
         };
 
-        dfmeta
+        Ok(dfmeta)
+    }
+}
+
+/// Series sharing the chunks of an arrow column, None (with a warning) if its type is not profiled
+/// Other types are cast to the one they are profiled as (a copy), see `series_type`
+///
+fn column_to_series(column: &frame::Column) -> FrameResult<Option<Series>> {
+    let to = match series_type(column.data_type()) {
+        Some(to) => to,
+        None => {
+            log::warn!("Column {} of type {:?} is not profiled", column.name(), column.data_type());
+            return Ok(None);
+        }
+    };
+    let chunks = column
+        .data()
+        .chunks()
+        .iter()
+        .map(|chunk| cast_chunk(chunk, &to))
+        .collect::<FrameResult<Vec<ArrayRef>>>();
+    match chunks {
+        Ok(chunks) => Ok(Some(
+            Series::try_from((column.name(), chunks)).map_err(|e| DataFrameError::ComputeError(e.to_string()))?,
+        )),
+        Err(e) => {
+            log::warn!("Column {} of type {:?} is not profiled: {}", column.name(), column.data_type(), e);
+            Ok(None)
+        }
+    }
+}
+
+/// Type a column is profiled as, None if it is not profiled
+/// Dates and timestamps are read as their number of days or time units since the epoch
+/// (only their range is profiled, see `is_temporal`), dictionaries by their values,
+/// unsigned 64 bit integers as floats (above i64::MAX they would be lost)
+///
+fn series_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        DataType::Int64 | DataType::Float64 | DataType::Boolean | DataType::Utf8 => Some(data_type.clone()),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::Date32(_)
+        | DataType::Date64(_)
+        | DataType::Timestamp(_, _) => Some(DataType::Int64),
+        DataType::UInt64 | DataType::Float32 => Some(DataType::Float64),
+        DataType::LargeUtf8 => Some(DataType::Utf8),
+        DataType::Dictionary(_, values) => series_type(values),
+        _ => None,
+    }
+}
+
+/// Dates and timestamps
+///
+pub fn is_temporal(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Date32(_) | DataType::Date64(_) | DataType::Timestamp(_, _))
+}
+
+fn cast_chunk(chunk: &ArrayRef, to: &DataType) -> FrameResult<ArrayRef> {
+    let chunk = match chunk.data_type() {
+        DataType::Dictionary(_, values) => arrow::compute::cast(chunk, values)?,
+        _ => chunk.clone(),
+    };
+    // days are 32 bit integers, widened from there
+    let chunk = match chunk.data_type() {
+        DataType::Date32(_) => arrow::compute::cast(&chunk, &DataType::Int32)?,
+        _ => chunk,
+    };
+    if chunk.data_type() == to {
+        Ok(chunk)
+    } else {
+        Ok(arrow::compute::cast(&chunk, to)?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{
+        Array, BinaryArray, DictionaryArray, Int32Array, Int64Array, StringArray, TimestampMillisecondArray,
+        UInt64Array,
+    };
+    use arrow::datatypes::{Int32Type, TimeUnit};
+
+    #[test]
+    fn record_batches_without_copy() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("amount", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("code", DataType::Int32, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(1), None, Some(3)])),
+            Arc::new(StringArray::from(vec![Some("a"), Some("b"), None])),
+            Arc::new(Int32Array::from(vec![7, 8, 9])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let amounts = batch.column(0).data().buffers()[0].as_ptr();
+
+        let dataframe = NcodeDataFrame::from_record_batches(schema, vec![batch.clone(), batch]).unwrap();
+        assert_eq!(dataframe.dataframe.height(), 6);
+        assert_eq!(dataframe.dataframe.column("code").unwrap().dtype(), &DataType::Int64);
+
+        let frame = dataframe.to_frame().unwrap();
+        let chunk = &frame.column(0).data().chunks()[0];
+        assert_eq!(chunk.data().buffers()[0].as_ptr(), amounts);
+        assert_eq!(frame.column(0).null_count(), 2);
+        assert_eq!(dataframe.profile().unwrap().nrows(), 6);
+    }
+
+    #[test]
+    fn profile_temporal_dictionary_and_unsigned_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("at", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+            Field::new(
+                "kind",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                true,
+            ),
+            Field::new("id", DataType::UInt64, true),
+            Field::new("raw", DataType::Binary, true),
+        ]));
+        let kinds: DictionaryArray<Int32Type> = vec!["a", "b", "a"].into_iter().collect();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from_vec(vec![1_600_000_000_000, 1_600_000_001_000, 0], None)),
+            Arc::new(kinds),
+            Arc::new(UInt64Array::from(vec![u64::MAX, 1, 2])),
+            Arc::new(BinaryArray::from(vec![b"x".as_ref(), b"y", b"z"])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+
+        let dataframe = NcodeDataFrame::from_record_batches(schema, vec![batch]).unwrap();
+        assert_eq!(dataframe.dataframe.width(), 3);
+        assert_eq!(dataframe.dataframe.column("at").unwrap().dtype(), &DataType::Int64);
+        assert_eq!(dataframe.dataframe.column("kind").unwrap().dtype(), &DataType::Utf8);
+        let ids = dataframe.dataframe.column("id").unwrap();
+        assert_eq!(ids.dtype(), &DataType::Float64);
+        assert_eq!(ids.null_count(), 0);
+
+        let profile = dataframe.profile().unwrap();
+        assert_eq!(profile.nrows(), 3);
+        let at = profile.column("at").unwrap();
+        assert_eq!(at.dtype(), Some(&DataType::Timestamp(TimeUnit::Millisecond, None)));
+        match at.features() {
+            ColumnFeatures::Numeric(numeric) => {
+                assert_eq!((numeric.min(), numeric.max()), (Some(0.0), Some(1_600_000_001_000.0)));
+                assert!(numeric.mean().is_none() && numeric.std().is_none() && numeric.hist().is_none());
+                assert!(numeric.outliers().is_none());
+            }
+            _ => panic!("expected the range of at"),
+        }
+        assert_eq!(profile.column("kind").unwrap().dtype(), Some(&DataType::Utf8));
+        assert_eq!(profile.column("id").unwrap().dtype(), Some(&DataType::UInt64));
+        assert!(profile.correlations().is_none());
+    }
+
+    #[test]
+    fn reload_profile_with_constant_column() {
+        let columns = vec![
+            Series::new("constant", &[1i64, 1, 1]),
+            Series::new("amount", &[1.0, 2.5, 4.0]),
+            Series::new("count", &[3i64, 2, 1]),
+        ];
+        let dataframe = NcodeDataFrame::new(DataFrame::new(columns).unwrap());
+        let profile = dataframe.profile().unwrap();
+        let correlations = profile.correlations().unwrap();
+        assert_eq!(correlations.values[0][1], None);
+        assert!((correlations.values[1][2].unwrap() + 1.0).abs() < 1e-9);
+
+        let saved = serde_json::to_string(&profile).unwrap();
+        let reloaded: DataFrameMeta = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded.correlations().unwrap().values, correlations.values);
+    }
+
+    #[test]
+    fn reload_profile_with_null_numeric_column() {
+        let columns = vec![
+            Series::new("empty", &[None::<f64>, None]),
+            Series::new("amount", &[Some(1.0), None]),
+        ];
+        let dataframe = NcodeDataFrame::new(DataFrame::new(columns).unwrap());
+        let saved = serde_json::to_string(&dataframe.profile().unwrap()).unwrap();
+        let reloaded: DataFrameMeta = serde_json::from_str(&saved).unwrap();
+        match reloaded.column("empty").unwrap().features() {
+            ColumnFeatures::Numeric(numeric) => assert_eq!((numeric.min(), numeric.mean()), (None, None)),
+            _ => panic!("expected numeric features"),
+        }
+        match reloaded.column("amount").unwrap().features() {
+            ColumnFeatures::Numeric(numeric) => assert_eq!((numeric.max(), numeric.std()), (Some(1.0), None)),
+            _ => panic!("expected numeric features"),
+        }
     }
 }
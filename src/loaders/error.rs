@@ -1,5 +1,7 @@
 // use std::error::Error;
 use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+use polars::prelude::PolarsError;
 
 
 #[derive(Debug, Clone, PartialEq)]
@@ -12,8 +14,33 @@ pub enum DataFrameError {
     NoneError,
     ArrowError(String),
     SqlError(String),
+    ParquetError(String),
+    /// Object storage misconfigured or unreachable
+    StorageError(String),
+    /// Column asked for by name is not in the data
+    ColumnNotFound(String),
 }
 
+impl std::fmt::Display for DataFrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataFrameError::MemoryError(message) => write!(f, "Out of memory: {}", message),
+            DataFrameError::ParseError(message) => write!(f, "Cannot parse input: {}", message),
+            DataFrameError::ComputeError(message) => write!(f, "{}", message),
+            DataFrameError::DivideByZero => write!(f, "Division by zero"),
+            DataFrameError::IoError(message) => write!(f, "{}", message),
+            DataFrameError::NoneError => write!(f, "No data"),
+            DataFrameError::ArrowError(message) => write!(f, "Cannot read arrow data: {}", message),
+            DataFrameError::SqlError(message) => write!(f, "Sql error: {}", message),
+            DataFrameError::ParquetError(message) => write!(f, "Cannot read parquet data: {}", message),
+            DataFrameError::StorageError(message) => write!(f, "Storage error: {}", message),
+            DataFrameError::ColumnNotFound(name) => write!(f, "Column {} not found", name),
+        }
+    }
+}
+
+impl std::error::Error for DataFrameError {}
+
 impl From<ArrowError> for DataFrameError {
     fn from(error: ArrowError) -> Self {
         DataFrameError::ArrowError(error.to_string())
@@ -26,4 +53,16 @@ impl From<::std::io::Error> for DataFrameError {
     }
 }
 
+impl From<PolarsError> for DataFrameError {
+    fn from(error: PolarsError) -> Self {
+        DataFrameError::ComputeError(error.to_string())
+    }
+}
+
+impl From<ParquetError> for DataFrameError {
+    fn from(error: ParquetError) -> Self {
+        DataFrameError::ParquetError(error.to_string())
+    }
+}
+
 pub type Result<T> = ::std::result::Result<T, DataFrameError>;
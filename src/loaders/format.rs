@@ -0,0 +1,100 @@
+// Detection of the format of an input, from its extension or its first bytes
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::loaders::ipc_format::{IPC_FILE_MAGIC, IPC_STREAM_MAGIC};
+
+/// Magic bytes at the start (and end) of a parquet file
+pub const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    Csv,
+    Parquet,
+    /// Json array of objects or newline delimited json
+    Json,
+    /// Arrow IPC file (Feather v2)
+    Ipc,
+    /// Arrow IPC stream
+    IpcStream,
+}
+
+impl InputFormat {
+    /// Format from the extension of a path, if known
+    ///
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" | "tsv" | "txt" => Some(InputFormat::Csv),
+            "parquet" | "pq" => Some(InputFormat::Parquet),
+            "json" | "ndjson" | "jsonl" => Some(InputFormat::Json),
+            "arrow" | "feather" | "ipc" => Some(InputFormat::Ipc),
+            "arrows" => Some(InputFormat::IpcStream),
+            _ => None,
+        }
+    }
+
+    /// Format from the first bytes of the content, if recognized
+    ///
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(PARQUET_MAGIC) {
+            return Some(InputFormat::Parquet);
+        }
+        if head.starts_with(IPC_FILE_MAGIC) {
+            return Some(InputFormat::Ipc);
+        }
+        if head.starts_with(IPC_STREAM_MAGIC) {
+            return Some(InputFormat::IpcStream);
+        }
+        match head.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'[') | Some(b'{') => Some(InputFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Format from a mime type (e.g. the content type of an http response), parameters ignored
+    ///
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next()?.trim().to_lowercase();
+        match mime.as_str() {
+            "text/csv" | "text/tab-separated-values" | "application/csv" => Some(InputFormat::Csv),
+            "application/vnd.apache.parquet" | "application/x-parquet" => Some(InputFormat::Parquet),
+            "application/json" | "application/x-ndjson" | "application/jsonl" => Some(InputFormat::Json),
+            "application/vnd.apache.arrow.file" => Some(InputFormat::Ipc),
+            "application/vnd.apache.arrow.stream" => Some(InputFormat::IpcStream),
+            _ => None,
+        }
+    }
+
+    /// Format of a local file: extension first, then magic bytes, csv otherwise
+    ///
+    pub fn detect(path: &str) -> std::io::Result<Self> {
+        if let Some(format) = InputFormat::from_path(path) {
+            return Ok(format);
+        }
+        let mut head = [0u8; 64];
+        let n = File::open(path)?.read(&mut head)?;
+        Ok(InputFormat::from_magic(&head[..n]).unwrap_or(InputFormat::Csv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_from_extension_and_magic() {
+        assert_eq!(InputFormat::from_path("data/events.PARQUET"), Some(InputFormat::Parquet));
+        assert_eq!(InputFormat::from_path("data/events.csv"), Some(InputFormat::Csv));
+        assert_eq!(InputFormat::from_path("data/events"), None);
+        assert_eq!(InputFormat::from_magic(b"PAR1\x15\x04"), Some(InputFormat::Parquet));
+        assert_eq!(InputFormat::from_magic(b"id,name"), None);
+        assert_eq!(InputFormat::from_path("cache/events.feather"), Some(InputFormat::Ipc));
+        assert_eq!(InputFormat::from_magic(b"ARROW1\0\0"), Some(InputFormat::Ipc));
+        assert_eq!(InputFormat::from_magic(b"  {\"id\": 1}"), Some(InputFormat::Json));
+        assert_eq!(InputFormat::from_content_type("text/csv; charset=utf-8"), Some(InputFormat::Csv));
+        assert_eq!(InputFormat::from_content_type("application/octet-stream"), None);
+    }
+}
@@ -1,34 +1,289 @@
 use std::sync::Arc;
-use std::collections::{HashMap, HashSet};
-use arrow::datatypes::{Field, Schema, DataType};
+use std::hash::Hash;
+use arrow::datatypes::{Field, Schema, DataType, TimeUnit};
 use arrow::array::*;
+use arrow::compute::{self, SortOptions};
 use arrow::record_batch::RecordBatch;
 use histo_fp::Histogram;
+use indexmap::{IndexMap, IndexSet};
 use noisy_float::prelude::*;
-use serde_json;
-// use serde_json::Value;
-use serde::{Deserialize, Serialize};
 use crate::loaders::error::*;
 // use std::hash::{Hash, Hasher};
 
 
-/// Generic type that encapsulates vecs of primitive types
-#[derive(Debug, Clone)]
+/// Values of a column by type, None where null
+/// Integers and floats of all widths are widened, dictionary arrays are read as their values,
+/// NaN floats are read as nulls, infinities are kept
+#[derive(Debug, Clone, PartialEq)]
 pub enum GenericVector {
-    I(Vec<i64>),
-    F(Vec<R64>),
-    S(Vec<String>),
+    B(Vec<Option<bool>>),
+    I(Vec<Option<i64>>),
+    U(Vec<Option<u64>>),
+    F(Vec<Option<N64>>),
+    S(Vec<Option<String>>),
+    /// Days since the epoch
+    Date32(Vec<Option<i32>>),
+    /// Milliseconds since the epoch
+    Date64(Vec<Option<i64>>),
+    Timestamp(Vec<Option<i64>>, TimeUnit),
+}
+
+macro_rules! map_vector {
+    ($vector:expr, $v:ident => $body:expr) => {
+        match $vector {
+            GenericVector::B($v) => $body,
+            GenericVector::I($v) => $body,
+            GenericVector::U($v) => $body,
+            GenericVector::F($v) => $body,
+            GenericVector::S($v) => $body,
+            GenericVector::Date32($v) => $body,
+            GenericVector::Date64($v) => $body,
+            GenericVector::Timestamp($v, _) => $body,
+        }
+    };
+}
+
+/// Distinct non null values, in order of first occurrence
+fn distinct<T: Eq + Hash>(values: Vec<Option<T>>) -> Vec<Option<T>> {
+    let set: IndexSet<T> = values.into_iter().flatten().collect();
+    set.into_iter().map(Some).collect()
 }
 
 impl GenericVector {
-    fn len(&self) -> usize {
-            match self {
-                Self::I(v) => v.len(),
-                Self::F(v) => v.len(),
-                Self::S(v) => v.len()
+    pub fn len(&self) -> usize {
+        map_vector!(self, v => v.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn null_count(&self) -> usize {
+        map_vector!(self, v => v.iter().filter(|value| value.is_none()).count())
+    }
+
+    /// Distinct non null values
+    ///
+    pub fn distinct(self) -> Self {
+        match self {
+            GenericVector::B(v) => GenericVector::B(distinct(v)),
+            GenericVector::I(v) => GenericVector::I(distinct(v)),
+            GenericVector::U(v) => GenericVector::U(distinct(v)),
+            GenericVector::F(v) => GenericVector::F(distinct(v)),
+            GenericVector::S(v) => GenericVector::S(distinct(v)),
+            GenericVector::Date32(v) => GenericVector::Date32(distinct(v)),
+            GenericVector::Date64(v) => GenericVector::Date64(distinct(v)),
+            GenericVector::Timestamp(v, unit) => GenericVector::Timestamp(distinct(v), unit),
+        }
+    }
+}
+
+/// Single value of a column, e.g. the result of an aggregate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    Boolean(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Utf8(String),
+    Date32(i32),
+    Date64(i64),
+    Timestamp(i64, TimeUnit),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Min,
+    Max,
+    Sum,
+}
+
+/// Type values of a column are read as: widest integer or float, values of dictionaries
+///
+fn value_type(data_type: &DataType) -> Result<DataType> {
+    Ok(match data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => DataType::Int64,
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => DataType::UInt64,
+        DataType::Float32 | DataType::Float64 => DataType::Float64,
+        DataType::Boolean | DataType::Utf8 | DataType::Date32(_) | DataType::Date64(_) | DataType::Timestamp(_, _) => {
+            data_type.clone()
+        }
+        DataType::LargeUtf8 => DataType::Utf8,
+        DataType::Dictionary(_, values) => value_type(values)?,
+        other => return Err(DataFrameError::ComputeError(format!("Unsupported column type {:?}", other))),
+    })
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Int64 | DataType::UInt64 | DataType::Float64 | DataType::Boolean)
+}
+
+/// Aggregate of the rows of each group, see DataFrame::group_by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregation {
+    Count,
+    Min,
+    Max,
+    Sum,
+    Mean,
+}
+
+impl Aggregation {
+    pub fn name(&self) -> &str {
+        match self {
+            Aggregation::Count => "count",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+            Aggregation::Sum => "sum",
+            Aggregation::Mean => "mean",
+        }
+    }
+
+    /// Type of the aggregate of a column of data_type
+    ///
+    fn data_type(&self, data_type: &DataType) -> Result<DataType> {
+        let data_type = match value_type(data_type)? {
+            DataType::Timestamp(unit, _) => DataType::Timestamp(unit, None),
+            other => other,
+        };
+        Ok(match (self, data_type) {
+            (Aggregation::Count, _) => DataType::UInt64,
+            (Aggregation::Mean, _) => DataType::Float64,
+            (Aggregation::Sum, DataType::Boolean) => DataType::UInt64,
+            (Aggregation::Sum, data_type) if is_numeric(&data_type) => data_type,
+            (Aggregation::Sum, data_type) => {
+                return Err(DataFrameError::ComputeError(format!("No sum of {:?} columns", data_type)))
+            }
+            (_, data_type) => data_type,
+        })
+    }
+}
+
+/// Value of a group key, nulls making a group of their own
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Null,
+    B(bool),
+    I(i64),
+    U(u64),
+    F(N64),
+    S(String),
+}
+
+fn keys(values: GenericVector) -> Vec<Key> {
+    fn to_keys<T>(values: Vec<Option<T>>, key: fn(T) -> Key) -> Vec<Key> {
+        values.into_iter().map(|v| v.map_or(Key::Null, key)).collect()
+    }
+    match values {
+        GenericVector::B(v) => to_keys(v, Key::B),
+        GenericVector::I(v) => to_keys(v, Key::I),
+        GenericVector::U(v) => to_keys(v, Key::U),
+        GenericVector::F(v) => to_keys(v, Key::F),
+        GenericVector::S(v) => to_keys(v, Key::S),
+        GenericVector::Date32(v) => to_keys(v, |d| Key::I(d as i64)),
+        GenericVector::Date64(v) => to_keys(v, Key::I),
+        GenericVector::Timestamp(v, _) => to_keys(v, Key::I),
+    }
+}
+
+/// Array of data_type from the results of an aggregation
+///
+fn scalars_to_array(data_type: &DataType, scalars: Vec<Option<Scalar>>) -> Result<ArrayRef> {
+    macro_rules! array {
+        ($array_type:ty, $variant:ident) => {{
+            let values: Vec<_> = scalars
+                .into_iter()
+                .map(|scalar| match scalar {
+                    Some(Scalar::$variant(v)) => Some(v),
+                    _ => None,
+                })
+                .collect();
+            Arc::new(<$array_type>::from(values)) as ArrayRef
+        }};
+    }
+
+    Ok(match data_type {
+        DataType::Boolean => array!(BooleanArray, Boolean),
+        DataType::Int64 => array!(Int64Array, Int),
+        DataType::UInt64 => array!(UInt64Array, UInt),
+        DataType::Float64 => array!(Float64Array, Float),
+        DataType::Date32(_) => array!(Date32Array, Date32),
+        DataType::Date64(_) => array!(Date64Array, Date64),
+        DataType::Utf8 => {
+            let values: Vec<Option<String>> = scalars
+                .into_iter()
+                .map(|scalar| match scalar {
+                    Some(Scalar::Utf8(v)) => Some(v),
+                    _ => None,
+                })
+                .collect();
+            Arc::new(StringArray::from(values.iter().map(|v| v.as_deref()).collect::<Vec<_>>()))
+        }
+        DataType::Timestamp(unit, _) => {
+            let values: Vec<Option<i64>> = scalars
+                .into_iter()
+                .map(|scalar| match scalar {
+                    Some(Scalar::Timestamp(v, _)) => Some(v),
+                    _ => None,
+                })
+                .collect();
+            match unit {
+                TimeUnit::Second => Arc::new(TimestampSecondArray::from_opt_vec(values, None)),
+                TimeUnit::Millisecond => Arc::new(TimestampMillisecondArray::from_opt_vec(values, None)),
+                TimeUnit::Microsecond => Arc::new(TimestampMicrosecondArray::from_opt_vec(values, None)),
+                TimeUnit::Nanosecond => Arc::new(TimestampNanosecondArray::from_opt_vec(values, None)),
+            }
+        }
+        other => return Err(DataFrameError::ComputeError(format!("Unsupported column type {:?}", other))),
+    })
+}
+
+/// Aggregate of the rows of each group (group_of gives the group of each row of column)
+/// Each chunk gives a partial aggregate per group, merged once every chunk is read:
+/// min of minimums, sum of sums, mean as the sum of sums over the number of values
+///
+fn aggregate_groups(column: &Column, group_of: &[u32], ngroups: usize, aggregation: Aggregation) -> Result<Vec<Option<Scalar>>> {
+    let (column, partial) = match aggregation {
+        Aggregation::Mean => (column.cast(&DataType::Float64)?, Aggregation::Sum),
+        other => (column.clone(), other),
+    };
+    let mut partials: Vec<Vec<Option<Scalar>>> = vec![vec![]; ngroups];
+    let mut counts: Vec<u64> = vec![0; ngroups];
+    let mut offset = 0;
+    for chunk in column.data().chunks() {
+        let mut rows: IndexMap<u32, Vec<u32>> = IndexMap::new();
+        for (i, group) in group_of[offset..offset + chunk.len()].iter().enumerate() {
+            rows.entry(*group).or_insert_with(Vec::new).push(i as u32);
+        }
+        for (group, indices) in rows {
+            let taken = column.with_chunks(vec![compute::take(chunk, &UInt32Array::from(indices), None)?]);
+            counts[group as usize] += taken.count() as u64;
+            if partial != Aggregation::Count {
+                partials[group as usize].push(taken.aggregation(partial)?);
             }
         }
+        offset += chunk.len();
+    }
 
+    let data_type = partial.data_type(column.data_type())?;
+    partials
+        .into_iter()
+        .zip(counts)
+        .map(|(values, count)| {
+            let merged = match partial {
+                Aggregation::Count => Some(Scalar::UInt(count)),
+                _ => {
+                    let field = Field::new(column.name(), data_type.clone(), true);
+                    Column::from_arrays(vec![scalars_to_array(&data_type, values)?], field)?.aggregation(partial)?
+                }
+            };
+            Ok(match (aggregation, merged) {
+                (Aggregation::Mean, Some(Scalar::Float(sum))) if count > 0 => Some(Scalar::Float(sum / count as f64)),
+                (Aggregation::Mean, _) => None,
+                (_, merged) => merged,
+            })
+        })
+        .collect()
 }
 
 #[derive(Clone)]
@@ -56,22 +311,30 @@ impl ChunkedArray {
         &self.chunks
     }
 
-    pub fn from_arrays(arrays: Vec<Arc<dyn Array>>) -> Self {
+    /// Chunks must all have the same type
+    ///
+    pub fn from_arrays(arrays: Vec<Arc<dyn Array>>) -> Result<Self> {
         let mut num_rows = 0;
         let mut null_count = 0;
-        let data_type = &arrays[0].data_type();
-
-        arrays.iter().for_each(|array| {
-            assert!(&array.data_type() == data_type);
+        let data_type = arrays.first().map(|array| array.data_type().clone());
+
+        for array in arrays.iter() {
+            if Some(array.data_type()) != data_type.as_ref() {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Chunks of types {:?} and {:?} in the same array",
+                    data_type,
+                    array.data_type()
+                )));
+            }
             num_rows += array.len();
             null_count += array.null_count();
-        });
+        }
 
-        ChunkedArray {
+        Ok(ChunkedArray {
             chunks: arrays,
             num_rows,
             null_count
-        }
+        })
     }
 
 }
@@ -88,13 +351,39 @@ impl Column {
         Ok(arrow::compute::concat(self.data().chunks())?)
     }
 
-    pub fn from_arrays(arrays: Vec<Arc<dyn Array>>, field: arrow::datatypes::Field ) -> Self {
-        for array in &arrays {
-            assert!(array.data_type() == field.data_type());
+    /// Column of field over arrays, which must have the type of field
+    ///
+    pub fn from_arrays(arrays: Vec<Arc<dyn Array>>, field: arrow::datatypes::Field ) -> Result<Self> {
+        if let Some(array) = arrays.iter().find(|array| array.data_type() != field.data_type()) {
+            return Err(DataFrameError::ComputeError(format!(
+                "Array of type {:?} in column {} of type {:?}",
+                array.data_type(),
+                field.name(),
+                field.data_type()
+            )));
         }
-        Column {
-            data: ChunkedArray::from_arrays(arrays),
+        Ok(Column {
+            data: ChunkedArray::from_arrays(arrays)?,
             field
+        })
+    }
+
+    /// Column of the same field over chunks taken from this one (slices, filtered or taken rows)
+    ///
+    fn with_chunks(&self, chunks: Vec<ArrayRef>) -> Column {
+        let mut num_rows = 0;
+        let mut null_count = 0;
+        for chunk in chunks.iter() {
+            num_rows += chunk.len();
+            null_count += chunk.null_count();
+        }
+        Column {
+            data: ChunkedArray {
+                chunks,
+                num_rows,
+                null_count,
+            },
+            field: self.field.clone(),
         }
     }
 
@@ -118,109 +407,288 @@ impl Column {
         self.field.data_type()
     }
 
-    pub fn uniques(&self) -> Result<GenericVector> {
-        let values = self.to_array().unwrap();
-        match self.data_type() {
-            DataType::Float64 => {
-                let mut uniques = HashSet::new();
-                let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
-                for i in 0..values.len() {
-                    let value = values.value(i) as f64;
-                    let value = r64(value);
-                    uniques.insert(value);
-                }
-                let v: Vec<_> = uniques.into_iter().collect();
-                let v = v.to_vec();
-                Ok(GenericVector::F(v))
-             },
-
-            DataType::Utf8 => {
-                let mut uniques: HashSet<String> = HashSet::new();
-                // let values: Arc<dyn Array> = self.to_array().unwrap();
-                let values = values.as_any().downcast_ref::<StringArray>().unwrap();
-
-                for i in 0..values.len() {
-                    let value: &str = values.value(i);
-                    uniques.insert(value.to_string());
-                }
-                let v: Vec<String> = uniques.into_iter().collect();
-                // let v = v.to_owned().to_vec();
-                Ok(GenericVector::S(v))
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
 
-            },
+    /// Column with every chunk cast to data_type
+    ///
+    pub fn cast(&self, data_type: &DataType) -> Result<Column> {
+        let chunks = self
+            .data
+            .chunks()
+            .iter()
+            .map(|chunk| Ok(compute::cast(chunk, data_type)?))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        let field = Field::new(self.name(), data_type.clone(), true);
+        Column::from_arrays(chunks, field)
+    }
 
-            DataType::Int64 => {
-                let mut uniques = HashSet::new();
-                let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
-                for i in 0..values.len() {
-                    let value = values.value(i);
-                    uniques.insert(value);
-                }
-                let v: Vec<_> = uniques.into_iter().collect();
-                let v = v.to_vec();
-                Ok(GenericVector::I(v))
-        },
+    /// Rows offset..offset + length, sharing the chunks they fall in
+    ///
+    pub fn slice(&self, offset: usize, length: usize) -> Column {
+        let mut chunks: Vec<ArrayRef> = vec![];
+        let mut start = offset;
+        let mut remaining = length;
+        for chunk in self.data.chunks() {
+            if remaining == 0 {
+                break;
+            }
+            if start >= chunk.len() {
+                start -= chunk.len();
+                continue;
+            }
+            let len = std::cmp::min(chunk.len() - start, remaining);
+            chunks.push(chunk.slice(start, len));
+            remaining -= len;
+            start = 0;
+        }
+        if chunks.is_empty() {
+            if let Some(chunk) = self.data.chunks().first() {
+                chunks.push(chunk.slice(0, 0));
+            }
+        }
+        self.with_chunks(chunks)
+    }
 
-            _ => panic!("Datatype not supported for uniques.")
+    /// Rows where predicate (one value per row of the column) is true, filtered chunk by chunk
+    ///
+    pub fn filter(&self, predicate: &BooleanArray) -> Result<Column> {
+        if predicate.len() != self.num_rows() {
+            return Err(DataFrameError::ComputeError(format!(
+                "Predicate of {} values for column {} of {} rows",
+                predicate.len(),
+                self.name(),
+                self.num_rows()
+            )));
+        }
+        let mut offset = 0;
+        let mut chunks: Vec<ArrayRef> = vec![];
+        for chunk in self.data.chunks() {
+            let mask = predicate.slice(offset, chunk.len());
+            let mask = mask.as_any().downcast_ref::<BooleanArray>().ok_or(DataFrameError::NoneError)?;
+            chunks.push(compute::filter(chunk.as_ref(), mask)?);
+            offset += chunk.len();
         }
+        Ok(self.with_chunks(chunks))
     }
 
-    pub fn nunique(&self) -> usize {
-        let unique_values = self.uniques().unwrap();
-        unique_values.len()
+    /// Rows at indices, in that order, as a single chunk
+    ///
+    pub fn take(&self, indices: &UInt32Array) -> Result<Column> {
+        let array = compute::take(&self.to_array()?, indices, None)?;
+        Ok(self.with_chunks(vec![array]))
     }
 
-    pub fn hist(&self, nbins: u64, density: bool) -> Histogram {
-        let values = self.to_array().unwrap();
-        let datatype = self.data_type();
-        let histogram = Histogram::with_buckets(nbins, None);
+    pub fn aggregation(&self, aggregation: Aggregation) -> Result<Option<Scalar>> {
+        Ok(match aggregation {
+            Aggregation::Count => Some(Scalar::UInt(self.count() as u64)),
+            Aggregation::Min => self.min()?,
+            Aggregation::Max => self.max()?,
+            Aggregation::Sum => self.sum()?,
+            Aggregation::Mean => self.mean()?.map(Scalar::Float),
+        })
+    }
 
-        match self.data_type() {
+    /// Chunks with the type values are read as (see GenericVector)
+    ///
+    fn value_chunks(&self) -> Result<(DataType, Vec<ArrayRef>)> {
+        let data_type = value_type(self.data_type())?;
+        if &data_type == self.data_type() {
+            return Ok((data_type, self.data.chunks().clone()));
+        }
+        let column = self.cast(&data_type)?;
+        Ok((data_type, column.data.chunks().clone()))
+    }
 
-            DataType::Int64 => {
-                let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
-                // Histogram makes sense only for numeric data
-                let mut histogram = Histogram::with_buckets(nbins, None);
-                for i in 0..values.len() {
-                    histogram.add(values.value(i) as f64);
+    /// All values, None where null
+    ///
+    pub fn values(&self) -> Result<GenericVector> {
+        let (data_type, chunks) = self.value_chunks()?;
+        macro_rules! collect {
+            ($array_type:ty, $map:expr) => {{
+                let mut values = Vec::with_capacity(self.num_rows());
+                for chunk in chunks.iter() {
+                    let array = chunk.as_any().downcast_ref::<$array_type>().ok_or(DataFrameError::NoneError)?;
+                    values.extend((0..array.len()).map(|i| if array.is_null(i) { None } else { $map(array.value(i)) }));
                 }
-                histogram
+                values
+            }};
+        }
 
-            },
+        Ok(match data_type {
+            DataType::Boolean => GenericVector::B(collect!(BooleanArray, Some)),
+            DataType::Int64 => GenericVector::I(collect!(Int64Array, Some)),
+            DataType::UInt64 => GenericVector::U(collect!(UInt64Array, Some)),
+            DataType::Float64 => GenericVector::F(collect!(Float64Array, N64::try_new)),
+            DataType::Utf8 => GenericVector::S(collect!(StringArray, |v: &str| Some(v.to_string()))),
+            DataType::Date32(_) => GenericVector::Date32(collect!(Date32Array, Some)),
+            DataType::Date64(_) => GenericVector::Date64(collect!(Date64Array, Some)),
+            DataType::Timestamp(unit, _) => {
+                let values = match unit {
+                    TimeUnit::Second => collect!(TimestampSecondArray, Some),
+                    TimeUnit::Millisecond => collect!(TimestampMillisecondArray, Some),
+                    TimeUnit::Microsecond => collect!(TimestampMicrosecondArray, Some),
+                    TimeUnit::Nanosecond => collect!(TimestampNanosecondArray, Some),
+                };
+                GenericVector::Timestamp(values, unit)
+            }
+            other => return Err(DataFrameError::ComputeError(format!("Unsupported column type {:?}", other))),
+        })
+    }
 
-            DataType::Float64 => {
-                let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
-                // Histogram makes sense only for numeric data
-                let mut histogram = Histogram::with_buckets(nbins, None);
+    /// Distinct non null values
+    ///
+    pub fn uniques(&self) -> Result<GenericVector> {
+        Ok(self.values()?.distinct())
+    }
 
-                for i in 0..values.len() {
-                    let value: f64 = values.value(i);
-                    histogram.add(value);
-                }
+    /// Number of distinct non null values
+    ///
+    pub fn nunique(&self) -> Result<usize> {
+        Ok(self.uniques()?.len())
+    }
+
+    /// Number of non null values
+    ///
+    pub fn count(&self) -> usize {
+        self.num_rows() - self.null_count()
+    }
 
-                // Iterate over buckets and do stuff with their range and count.
-                for bucket in histogram.buckets() {
-                    println!("start:{} end:{} count:{}", bucket.start(), bucket.end(), bucket.count());
-                    // bins.push(bucket.start().to_string());
-                    // TODO add probablility here
-                    // if density {
-                    //     counters.push(bucket.count() as f64 / values.len() as f64);
-                    // }
-                    // else {
-                    //     counters.push(bucket.count() as f64);
-                    // }
+    /// Smallest non null value (false before true, strings in byte order)
+    ///
+    pub fn min(&self) -> Result<Option<Scalar>> {
+        self.aggregate(Aggregate::Min)
+    }
+
+    pub fn max(&self) -> Result<Option<Scalar>> {
+        self.aggregate(Aggregate::Max)
+    }
+
+    /// Sum of non null numbers, number of true values for booleans
+    /// Integer sums wrap around on overflow
+    ///
+    pub fn sum(&self) -> Result<Option<Scalar>> {
+        self.aggregate(Aggregate::Sum)
+    }
 
+    /// Mean of non null numbers (share of true values for booleans), None if there are none
+    ///
+    pub fn mean(&self) -> Result<Option<f64>> {
+        if !is_numeric(&value_type(self.data_type())?) {
+            return Err(DataFrameError::ComputeError(format!("No mean of {:?} column {}", self.data_type(), self.name())));
+        }
+        let count = self.count();
+        match self.cast(&DataType::Float64)?.sum()? {
+            Some(Scalar::Float(sum)) if count > 0 => Ok(Some(sum / count as f64)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Aggregate the chunks with arrow kernels, then the results of the chunks
+    ///
+    fn aggregate(&self, aggregate: Aggregate) -> Result<Option<Scalar>> {
+        let (data_type, chunks) = self.value_chunks()?;
+        macro_rules! fold {
+            ($array_type:ty, $min:path, $max:path, $sum:expr, $add:expr) => {{
+                let mut result = None;
+                for chunk in chunks.iter() {
+                    let array = chunk.as_any().downcast_ref::<$array_type>().ok_or(DataFrameError::NoneError)?;
+                    let value = match aggregate {
+                        Aggregate::Min => $min(array),
+                        Aggregate::Max => $max(array),
+                        Aggregate::Sum => $sum(array),
+                    };
+                    result = match (result, value) {
+                        (Some(r), Some(v)) => Some(match aggregate {
+                            Aggregate::Min => if v < r { v } else { r },
+                            Aggregate::Max => if v > r { v } else { r },
+                            Aggregate::Sum => $add(r, v),
+                        }),
+                        (r, None) => r,
+                        (None, v) => v,
+                    };
                 }
-                histogram
+                result
+            }};
+        }
+        macro_rules! numeric {
+            ($array_type:ty) => {{
+                fold!($array_type, compute::min, compute::max, compute::sum, |r, v| r + v)
+            }};
+        }
+        let not_summed = || {
+            Err(DataFrameError::ComputeError(format!(
+                "No sum of {:?} column {}",
+                self.data_type(),
+                self.name()
+            )))
+        };
+
+        Ok(match (&data_type, aggregate) {
+            (DataType::Int64, _) => numeric!(Int64Array).map(Scalar::Int),
+            (DataType::UInt64, _) => numeric!(UInt64Array).map(Scalar::UInt),
+            (DataType::Float64, _) => numeric!(Float64Array).map(Scalar::Float),
+            (DataType::Boolean, _) => match self.cast(&DataType::UInt64)?.aggregate(aggregate)? {
+                Some(Scalar::UInt(v)) if aggregate != Aggregate::Sum => Some(Scalar::Boolean(v != 0)),
+                other => other,
             },
+            (DataType::Utf8, Aggregate::Sum)
+            | (DataType::Date32(_), Aggregate::Sum)
+            | (DataType::Date64(_), Aggregate::Sum)
+            | (DataType::Timestamp(_, _), Aggregate::Sum) => return not_summed(),
+            (DataType::Utf8, _) => {
+                fold!(StringArray, compute::min_string, compute::max_string, |_| None::<&str>, |r, _| r)
+                    .map(|v| Scalar::Utf8(v.to_string()))
+            }
+            (DataType::Date32(_), _) => numeric!(Date32Array).map(Scalar::Date32),
+            (DataType::Date64(_), _) => numeric!(Date64Array).map(Scalar::Date64),
+            (DataType::Timestamp(unit, _), _) => {
+                let value = match unit {
+                    TimeUnit::Second => numeric!(TimestampSecondArray),
+                    TimeUnit::Millisecond => numeric!(TimestampMillisecondArray),
+                    TimeUnit::Microsecond => numeric!(TimestampMicrosecondArray),
+                    TimeUnit::Nanosecond => numeric!(TimestampNanosecondArray),
+                };
+                value.map(|v| Scalar::Timestamp(v, unit.clone()))
+            }
+            (other, _) => return Err(DataFrameError::ComputeError(format!("Unsupported column type {:?}", other))),
+        })
+    }
 
-            _ => panic!("Unsupported type for histogram")
+    /// Histogram of the non null values of a numeric column
+    ///
+    pub fn hist(&self, nbins: u64, _density: bool) -> Result<Histogram> {
+        if !is_numeric(&value_type(self.data_type())?) {
+            return Err(DataFrameError::ComputeError(format!(
+                "No histogram of {:?} column {}",
+                self.data_type(),
+                self.name()
+            )));
         }
+        let mut histogram = Histogram::with_buckets(nbins, None);
+        // infinities have no bucket
+        if let GenericVector::F(values) = self.cast(&DataType::Float64)?.values()? {
+            values
+                .into_iter()
+                .flatten()
+                .filter(|value| value.is_finite())
+                .for_each(|value| histogram.add(value.raw()));
+        }
+        for bucket in histogram.buckets() {
+            log::trace!("start:{} end:{} count:{}", bucket.start(), bucket.end(), bucket.count());
+        }
+        Ok(histogram)
     }
 
-    pub fn is_categorical(&self, threshold: f64) -> bool {
-        let ratio: f64 = self.nunique() as f64 / self.num_rows() as f64;
-        ratio < threshold
+    /// Whether few distinct values repeat over the non null rows
+    ///
+    pub fn is_categorical(&self, threshold: f64) -> Result<bool> {
+        let count = self.count();
+        if count == 0 {
+            return Ok(false);
+        }
+        Ok((self.nunique()? as f64 / count as f64) < threshold)
     }
 }
 
@@ -259,28 +727,7 @@ impl Column {
 // }
 
 
-#[derive(Serialize, Deserialize)]
-pub struct DataFrameMeta {
-    datasource: String,
-    hash: String,
-    profile: ProfileMeta
-}
-#[derive(Serialize, Deserialize)]
-pub struct ProfileMeta {
-    nrows: usize,
-    ncols: usize,
-    columns: HashMap<String, ColumnMeta>,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ColumnMeta {
-    hash: Vec<u8>,
-    nunique: usize,
-    count: usize,
-    categorical: bool,
-    types: Vec<DataType>,
-}
-
+#[derive(Clone)]
 pub struct DataFrame {
     schema: Arc<Schema>,
     columns: Vec<Column>,
@@ -293,18 +740,17 @@ impl DataFrame {
 
     /// Return meta-data (Field) of column with name
     ///
-    pub fn column_by_name(&self, name: &str) -> &Field {
-        let column_number = self.schema.column_with_name(name).expect("Column not found!");
-        let column = self.schema.field(column_number.0);
-        column
+    pub fn column_by_name(&self, name: &str) -> Result<&Field> {
+        Ok(self.schema.field(self.column_index(name)?))
     }
 
     /// Return index of column with name
     ///
-    pub fn column_index(&self, name: &str) -> usize {
-        let column_number = self.schema.column_with_name(name).expect("Column not found!");
-        column_number.0
-
+    pub fn column_index(&self, name: &str) -> Result<usize> {
+        self.schema
+            .column_with_name(name)
+            .map(|(i, _)| i)
+            .ok_or_else(|| DataFrameError::ColumnNotFound(name.to_string()))
     }
 
     pub fn num_columns(&self) -> usize {
@@ -312,7 +758,16 @@ impl DataFrame {
     }
 
     pub fn num_rows(&self) -> usize {
-        self.columns[0].data().num_rows()
+        self.columns.first().map_or(0, |c| c.num_rows())
+    }
+
+    /// Return column with name
+    ///
+    pub fn column_with_name(&self, name: &str) -> Result<&Column> {
+        self.columns
+            .iter()
+            .find(|c| c.name() == name)
+            .ok_or_else(|| DataFrameError::ColumnNotFound(name.to_string()))
     }
 
     /// Return column at index i
@@ -334,9 +789,9 @@ impl DataFrame {
         coltypes
     }
 
-    pub fn from_record_batches(schema: Arc<Schema>, record_batches: Vec<RecordBatch>) -> Self {
+    pub fn from_record_batches(schema: Arc<Schema>, record_batches: Vec<RecordBatch>) -> Result<Self> {
         if record_batches.is_empty() {
-            panic!("Error. No record batches found");
+            return Err(DataFrameError::NoneError);
         }
 
         let numcols = record_batches[0].num_columns();
@@ -351,63 +806,157 @@ impl DataFrame {
         .iter()
         .enumerate()
         .map(|(i, array)| Column::from_arrays(array.to_owned(), schema.field(i).clone()))
-        .collect();
+        .collect::<Result<Vec<Column>>>()?;
 
-        DataFrame {
+        Ok(DataFrame {
             schema,
             columns
+        })
+    }
+
+    /// Dataframe of columns sharing their chunks, e.g. the series of a polars dataframe
+    ///
+    pub fn from_columns(columns: Vec<Column>) -> Self {
+        let fields: Vec<Field> = columns.iter().map(|c| c.field().clone()).collect();
+        DataFrame {
+            schema: Arc::new(Schema::new(fields)),
+            columns,
         }
     }
 
-    /// profile dataframe
-    /// Return json of metadata
-    ///
-    pub fn profile(&self) -> String {
-        // let ncols = self.num_columns();
-        let nrows = self.num_rows();
-        let colnames = self.columns();
-        let coltypes: Vec<DataType> = self.column_types();
-        // meta data for single column
-        let mut columns_meta: HashMap<String, ColumnMeta> = HashMap::new();
-
-        // extract profile of each column
-        for name in colnames.iter() {
-            let colidx = self.column_index(name);
-            let singlecol = self.column(colidx);
-            let nunique = singlecol.nunique();
-            let coltype: DataType = singlecol.data_type().clone();
-            let categorical = singlecol.is_categorical(0.2);
-
-            let colmeta = ColumnMeta{
-                hash: vec![],
-                nunique,
-                count: nrows,
-                categorical,
-                types: vec![coltype]
-            };
-            columns_meta.insert(name.to_string(), colmeta);
-            // let som = col_to_string_arrays(singlecol);
-            // let arr:Vec<&arrow::array::PrimitiveArray<f64> = col_to_prim_arrays(singlecol);
-            // println!("{:?}", som);
+    /// Dataframe of the columns with names, in that order
+    ///
+    pub fn select(&self, names: &[&str]) -> Result<DataFrame> {
+        let columns = names
+            .iter()
+            .map(|name| self.column_with_name(name).map(|c| c.clone()))
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(DataFrame::from_columns(columns))
+    }
 
-        }
+    /// Rows where predicate is true, e.g. built with the comparison kernels of arrow::compute
+    ///
+    pub fn filter(&self, predicate: &BooleanArray) -> Result<DataFrame> {
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| c.filter(predicate))
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(DataFrame::from_columns(columns))
+    }
 
-        let profilemeta = ProfileMeta {
-            nrows,
-            ncols: colnames.len(),
-            columns: columns_meta
-        };
+    /// Rows offset..offset + length (fewer past the end), without copying
+    ///
+    pub fn slice(&self, offset: usize, length: usize) -> DataFrame {
+        let columns = self.columns.iter().map(|c| c.slice(offset, length)).collect();
+        DataFrame::from_columns(columns)
+    }
+
+    pub fn head(&self, n: usize) -> DataFrame {
+        self.slice(0, n)
+    }
 
-        let dfmeta = DataFrameMeta{
-            datasource: String::from(""),
-            hash: String::from(""),
-            profile: profilemeta
+    pub fn tail(&self, n: usize) -> DataFrame {
+        let n = std::cmp::min(n, self.num_rows());
+        self.slice(self.num_rows() - n, n)
+    }
+
+    /// Rows ordered by the values of column by, nulls first
+    /// The by column is concatenated into one array to be sorted (a copy),
+    /// and every column is copied into a single chunk in sorted order
+    ///
+    pub fn sort(&self, by: &str, descending: bool) -> Result<DataFrame> {
+        let options = SortOptions {
+            descending,
+            nulls_first: true,
         };
+        let indices = compute::sort_to_indices(&self.column_with_name(by)?.to_array()?, Some(options))?;
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| c.take(&indices))
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(DataFrame::from_columns(columns))
+    }
 
-        let profile_str = serde_json::to_string(&dfmeta).unwrap();
-        profile_str
+    /// One row per distinct value of the keys (in order of first occurrence, nulls making a group),
+    /// with the key columns and one column per aggregate named column_aggregation, e.g. amount_sum
+    /// Key values are read into memory, aggregated columns chunk by chunk (see aggregate_groups)
+    ///
+    pub fn group_by(&self, keys: &[&str], aggregates: &[(&str, Aggregation)]) -> Result<DataFrame> {
+        let key_values = keys
+            .iter()
+            .map(|name| Ok(self::keys(self.column_with_name(name)?.values()?)))
+            .collect::<Result<Vec<Vec<Key>>>>()?;
+
+        // first row of each group, and the group of each row
+        let mut groups: IndexMap<Vec<Key>, u32> = IndexMap::new();
+        let mut group_of: Vec<u32> = Vec::with_capacity(self.num_rows());
+        for row in 0..self.num_rows() {
+            let key = key_values.iter().map(|values| values[row].clone()).collect();
+            let entry = groups.entry(key);
+            group_of.push(entry.index() as u32);
+            entry.or_insert(row as u32);
+        }
+
+        let first_rows = UInt32Array::from(groups.values().copied().collect::<Vec<u32>>());
+        let mut columns = keys
+            .iter()
+            .map(|name| self.column_with_name(name)?.take(&first_rows))
+            .collect::<Result<Vec<Column>>>()?;
+
+        for (name, aggregation) in aggregates {
+            let column = self.column_with_name(name)?;
+            let data_type = aggregation.data_type(column.data_type())?;
+            let scalars = aggregate_groups(column, &group_of, groups.len(), *aggregation)?;
+            let field = Field::new(&format!("{}_{}", name, aggregation.name()), data_type.clone(), true);
+            columns.push(Column::from_arrays(vec![scalars_to_array(&data_type, scalars)?], field)?);
+        }
+        Ok(DataFrame::from_columns(columns))
+    }
+
+    /// Rows of all frames, one after the other, sharing their chunks
+    /// Frames must have the same column names and types, in the same order
+    ///
+    pub fn concat(frames: &[DataFrame]) -> Result<DataFrame> {
+        let first = frames
+            .first()
+            .ok_or_else(|| DataFrameError::ComputeError(String::from("No dataframes to concatenate")))?;
+        for frame in &frames[1..] {
+            if frame.columns() != first.columns() || frame.column_types() != first.column_types() {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Cannot concatenate columns {:?} with {:?}",
+                    frame.schema().fields(),
+                    first.schema().fields()
+                )));
+            }
+        }
+        let columns = first
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let chunks: Vec<ArrayRef> = frames
+                    .iter()
+                    .flat_map(|frame| frame.columns[i].data().chunks().clone())
+                    .collect();
+                let nullable = frames.iter().any(|frame| frame.columns[i].field().is_nullable());
+                Column::from_arrays(chunks, Field::new(column.name(), column.data_type().clone(), nullable))
+            })
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(DataFrame::from_columns(columns))
     }
 
+    /// All columns as a single record batch, e.g. to write them out
+    ///
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        let arrays = self
+            .columns
+            .iter()
+            .map(|c| c.to_array())
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(RecordBatch::try_new(self.schema.clone(), arrays)?)
+    }
 }
 
 
@@ -419,4 +968,104 @@ mod tests {
     #[test]
     fn create_table_from_csv() {
     }
+
+    #[test]
+    fn aggregate_typed_values_with_nulls() {
+        let field = Field::new("amount", DataType::Int32, true);
+        let column = Column::from_arrays(
+            vec![
+                Arc::new(Int32Array::from(vec![Some(3), None, Some(-1)])),
+                Arc::new(Int32Array::from(vec![None, Some(3)])),
+            ],
+            field,
+        )
+        .unwrap();
+        assert_eq!(column.values().unwrap(), GenericVector::I(vec![Some(3), None, Some(-1), None, Some(3)]));
+        assert_eq!(column.uniques().unwrap(), GenericVector::I(vec![Some(3), Some(-1)]));
+        assert_eq!(column.count(), 3);
+        assert_eq!(column.min().unwrap(), Some(Scalar::Int(-1)));
+        assert_eq!(column.max().unwrap(), Some(Scalar::Int(3)));
+        assert_eq!(column.sum().unwrap(), Some(Scalar::Int(5)));
+        assert_eq!(column.mean().unwrap(), Some(5.0 / 3.0));
+
+        let dictionary: DictionaryArray<arrow::datatypes::Int8Type> = vec!["b", "a", "b"].into_iter().collect();
+        let field = Field::new(
+            "code",
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+            true,
+        );
+        let column = Column::from_arrays(vec![Arc::new(dictionary)], field).unwrap();
+        assert_eq!(column.nunique().unwrap(), 2);
+        assert_eq!(column.min().unwrap(), Some(Scalar::Utf8(String::from("a"))));
+        assert!(column.sum().is_err());
+
+        let field = Field::new("flag", DataType::Boolean, true);
+        let column =
+            Column::from_arrays(vec![Arc::new(BooleanArray::from(vec![Some(true), None, Some(true)]))], field).unwrap();
+        assert!(Column::from_arrays(vec![Arc::new(Int32Array::from(vec![1]))], Field::new("flag", DataType::Boolean, true)).is_err());
+        assert_eq!(column.min().unwrap(), Some(Scalar::Boolean(true)));
+        assert_eq!(column.sum().unwrap(), Some(Scalar::UInt(2)));
+
+        let field = Field::new("rate", DataType::Float64, true);
+        let rates = Float64Array::from(vec![Some(1.5), Some(f64::INFINITY), Some(f64::NAN), None]);
+        let column = Column::from_arrays(vec![Arc::new(rates)], field).unwrap();
+        assert_eq!(
+            column.values().unwrap(),
+            GenericVector::F(vec![Some(n64(1.5)), Some(n64(f64::INFINITY)), None, None])
+        );
+    }
+
+    #[test]
+    fn filter_group_and_sort_chunked_frame() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("kind", DataType::Utf8, true),
+            Field::new("amount", DataType::Int64, true),
+        ]));
+        let batch = |kinds: Vec<&str>, amounts: Vec<i64>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(StringArray::from(kinds)), Arc::new(Int64Array::from(amounts))],
+            )
+            .unwrap()
+        };
+        let df = DataFrame::from_record_batches(
+            schema.clone(),
+            vec![
+                batch(vec!["debit", "credit", "debit"], vec![10, 5, 7]),
+                batch(vec!["debit", "credit"], vec![1, 20]),
+            ],
+        )
+        .unwrap();
+
+        let tail = df.tail(3);
+        assert_eq!(tail.num_rows(), 3);
+        assert_eq!(tail.column(1).data().num_chunks(), 2);
+
+        let kinds = df.column_with_name("kind").unwrap().to_array().unwrap();
+        let kinds = kinds.as_any().downcast_ref::<StringArray>().unwrap();
+        let debits = df.filter(&compute::eq_utf8_scalar(kinds, "debit").unwrap()).unwrap();
+        assert_eq!(debits.num_rows(), 3);
+        assert_eq!(debits.column_with_name("amount").unwrap().sum().unwrap(), Some(Scalar::Int(18)));
+
+        let groups = df
+            .group_by(&["kind"], &[("amount", Aggregation::Sum), ("amount", Aggregation::Count)])
+            .unwrap();
+        assert_eq!(groups.columns(), vec!["kind", "amount_sum", "amount_count"]);
+        assert_eq!(groups.column(0).values().unwrap(), GenericVector::S(vec![Some("debit".into()), Some("credit".into())]));
+        assert_eq!(groups.column(1).values().unwrap(), GenericVector::I(vec![Some(18), Some(25)]));
+        // groups spanning both chunks
+        let groups = df
+            .group_by(&["kind"], &[("amount", Aggregation::Min), ("amount", Aggregation::Max), ("amount", Aggregation::Mean)])
+            .unwrap();
+        assert_eq!(groups.column(1).values().unwrap(), GenericVector::I(vec![Some(1), Some(5)]));
+        assert_eq!(groups.column(2).values().unwrap(), GenericVector::I(vec![Some(10), Some(20)]));
+        assert_eq!(groups.column(3).values().unwrap(), GenericVector::F(vec![Some(n64(6.0)), Some(n64(12.5))]));
+
+        let sorted = df.sort("amount", true).unwrap().head(2);
+        assert_eq!(sorted.column(1).values().unwrap(), GenericVector::I(vec![Some(20), Some(10)]));
+
+        let both = DataFrame::concat(&[df.clone(), debits]).unwrap();
+        assert_eq!(both.num_rows(), 8);
+        assert!(DataFrame::concat(&[df.clone(), df.select(&["amount"]).unwrap()]).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+// Arrow IPC files (Feather v2) and streams, read and written as record batches without conversion
+
+use arrow::datatypes::Schema;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use std::io::{Read, Seek, Write};
+use std::sync::Arc;
+
+use crate::loaders::error::Result;
+
+/// Magic bytes at the start of an IPC file
+pub const IPC_FILE_MAGIC: &[u8; 6] = b"ARROW1";
+/// Continuation marker starting each message of an IPC stream
+pub const IPC_STREAM_MAGIC: &[u8; 4] = &[0xff, 0xff, 0xff, 0xff];
+
+/// Read all record batches of an IPC file
+///
+pub fn read_file<R: Read + Seek>(reader: R) -> Result<(Arc<Schema>, Vec<RecordBatch>)> {
+    let reader = FileReader::try_new(reader)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<std::result::Result<Vec<RecordBatch>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// Read all record batches of an IPC stream, the reader need not be seekable
+///
+pub fn read_stream<R: Read>(reader: R) -> Result<(Arc<Schema>, Vec<RecordBatch>)> {
+    let reader = StreamReader::try_new(reader)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<std::result::Result<Vec<RecordBatch>, _>>()?;
+    Ok((schema, batches))
+}
+
+pub fn write_file<W: Write>(writer: W, schema: &Schema, batches: &[RecordBatch]) -> Result<()> {
+    let mut writer = FileWriter::try_new(writer, schema)?;
+    for batch in batches.iter() {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn write_stream<W: Write>(writer: W, schema: &Schema, batches: &[RecordBatch]) -> Result<()> {
+    let mut writer = StreamWriter::try_new(writer, schema)?;
+    for batch in batches.iter() {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field};
+    use std::io::Cursor;
+
+    #[test]
+    fn roundtrip_file_and_stream() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(1), None, Some(3)])),
+            Arc::new(StringArray::from(vec![Some("a"), Some("b"), None])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+
+        let mut file = vec![];
+        write_file(&mut file, &schema, &[batch.clone()]).unwrap();
+        assert!(file.starts_with(IPC_FILE_MAGIC));
+        let (read_schema, batches) = read_file(Cursor::new(file)).unwrap();
+        assert_eq!(read_schema, schema);
+        assert_eq!(batches[0].num_rows(), 3);
+
+        let mut stream = vec![];
+        write_stream(&mut stream, &schema, &[batch]).unwrap();
+        let (_, batches) = read_stream(&stream[..]).unwrap();
+        assert_eq!(batches[0].column(0).null_count(), 1);
+    }
+}
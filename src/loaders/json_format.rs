@@ -0,0 +1,219 @@
+// JSON arrays and newline delimited JSON read as arrow record batches
+//
+// Nested objects are flattened into dotted column paths (address.city),
+// arrays are kept as json text in list columns, profiled by NcodeDataFrame
+
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::loaders::error::{DataFrameError, Result};
+
+/// Kind of a json value, as reported in schemas and list element types
+///
+pub fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_i64() => "int",
+        Value::Number(_) => "float",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
+
+/// What was seen of a field across records
+///
+#[derive(Debug, Default)]
+pub struct FieldSchema {
+    /// Number of records where the field is present (possibly null)
+    pub present: usize,
+    /// Kinds of values seen, with counts
+    pub kinds: BTreeMap<&'static str, usize>,
+}
+
+impl FieldSchema {
+    fn data_type(&self) -> DataType {
+        let kinds: HashSet<&str> = self.kinds.keys().copied().filter(|k| *k != "null").collect();
+        let only = |allowed: &[&str]| !kinds.is_empty() && kinds.iter().all(|k| allowed.contains(k));
+
+        if only(&["int"]) {
+            DataType::Int64
+        } else if only(&["int", "float"]) {
+            DataType::Float64
+        } else if only(&["bool"]) {
+            DataType::Boolean
+        } else {
+            DataType::Utf8
+        }
+    }
+
+    /// A field is a list column as soon as one of its values is a list
+    ///
+    pub fn is_list(&self) -> bool {
+        self.kinds.contains_key("list")
+    }
+}
+
+/// Schema inferred across all records, fields in order of first appearance
+///
+#[derive(Debug, Default)]
+pub struct JsonSchema {
+    pub records: usize,
+    pub fields: IndexMap<String, FieldSchema>,
+}
+
+impl JsonSchema {
+    /// Fields missing from some records, with the number of records having them
+    ///
+    pub fn partial_fields(&self) -> Vec<(&str, usize)> {
+        self.fields
+            .iter()
+            .filter(|(_, f)| f.present < self.records)
+            .map(|(name, f)| (name.as_str(), f.present))
+            .collect()
+    }
+
+    /// Names of list columns
+    ///
+    pub fn list_columns(&self) -> HashSet<String> {
+        self.fields.iter().filter(|(_, f)| f.is_list()).map(|(n, _)| n.clone()).collect()
+    }
+
+    /// Number of records having each field
+    ///
+    pub fn presence(&self) -> HashMap<String, usize> {
+        self.fields.iter().map(|(n, f)| (n.clone(), f.present)).collect()
+    }
+}
+
+/// Leaves of a record, objects are walked with their keys joined by dots
+///
+fn flatten<'a>(prefix: &str, value: &'a Value, out: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter() {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, v, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value)),
+    }
+}
+
+pub struct JsonReader<R>
+where R: Read
+{
+    reader: R,
+}
+
+impl<R> JsonReader<R>
+where R: Read
+{
+    pub fn new(reader: R) -> Self {
+        JsonReader { reader }
+    }
+
+    /// Read a json array of objects, or objects one after the other (ndjson)
+    /// Every record is kept in memory, the schema is inferred over all of them
+    ///
+    fn records(self) -> Result<Vec<Value>> {
+        let mut records: Vec<Value> = vec![];
+        for value in serde_json::Deserializer::from_reader(self.reader).into_iter::<Value>() {
+            match value.map_err(|e| DataFrameError::ParseError(e.to_string()))? {
+                Value::Array(values) => records.extend(values),
+                value => records.push(value),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Infer the schema over all records and build a single record batch
+    ///
+    pub fn finish(self) -> Result<(JsonSchema, RecordBatch)> {
+        let records = self.records()?;
+        if records.is_empty() {
+            return Err(DataFrameError::ParseError(String::from("No json records found")));
+        }
+
+        let mut schema = JsonSchema { records: records.len(), fields: IndexMap::new() };
+        let mut rows: Vec<HashMap<String, &Value>> = Vec::with_capacity(records.len());
+
+        for (i, record) in records.iter().enumerate() {
+            if !record.is_object() {
+                return Err(DataFrameError::ParseError(format!(
+                    "Record {} is a {}, expected an object",
+                    i,
+                    json_kind(record)
+                )));
+            }
+            let mut leaves: Vec<(String, &Value)> = vec![];
+            flatten("", record, &mut leaves);
+            for (path, value) in leaves.iter() {
+                let field = schema.fields.entry(path.clone()).or_insert_with(FieldSchema::default);
+                field.present += 1;
+                *field.kinds.entry(json_kind(value)).or_insert(0) += 1;
+            }
+            rows.push(leaves.into_iter().collect());
+        }
+
+        let mut fields: Vec<Field> = vec![];
+        let mut columns: Vec<ArrayRef> = vec![];
+        for (name, field) in schema.fields.iter() {
+            let values = rows.iter().map(|row| row.get(name).copied().filter(|v| !v.is_null()));
+            let data_type = field.data_type();
+            let array: ArrayRef = match data_type {
+                DataType::Int64 => Arc::new(Int64Array::from(values.map(|v| v.and_then(|v| v.as_i64())).collect::<Vec<_>>())),
+                DataType::Float64 => {
+                    Arc::new(Float64Array::from(values.map(|v| v.and_then(|v| v.as_f64())).collect::<Vec<_>>()))
+                }
+                DataType::Boolean => {
+                    Arc::new(BooleanArray::from(values.map(|v| v.and_then(|v| v.as_bool())).collect::<Vec<_>>()))
+                }
+                _ => {
+                    // strings as they are, anything else (lists, mixed kinds) as json text
+                    let text: Vec<Option<String>> = values
+                        .map(|v| {
+                            v.map(|v| match v {
+                                Value::String(s) => s.clone(),
+                                other => other.to_string(),
+                            })
+                        })
+                        .collect();
+                    Arc::new(StringArray::from(text.iter().map(|t| t.as_deref()).collect::<Vec<Option<&str>>>()))
+                }
+            };
+            fields.push(Field::new(name, data_type, true));
+            columns.push(array);
+        }
+
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+        Ok((schema, batch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatten_ndjson_with_partial_fields() {
+        let data = r#"{"id": 1, "address": {"city": "Rome"}, "tags": ["a", "b"]}
+{"id": 2.5, "tags": []}"#;
+        let (schema, batch) = JsonReader::new(data.as_bytes()).finish().unwrap();
+
+        assert_eq!(schema.records, 2);
+        assert_eq!(batch.num_rows(), 2);
+        let names: Vec<&String> = schema.fields.keys().collect();
+        assert_eq!(names, vec!["id", "address.city", "tags"]);
+        assert_eq!(schema.fields["id"].data_type(), DataType::Float64);
+        assert_eq!(schema.partial_fields(), vec![("address.city", 1)]);
+        assert!(schema.list_columns().contains("tags"));
+    }
+}
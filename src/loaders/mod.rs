@@ -1,6 +1,14 @@
 pub mod s3_connector;
+pub mod sink;
+pub mod source;
+pub mod compression;
 pub mod csv_format;
+pub mod format;
+pub mod ipc_format;
+pub mod json_format;
+pub mod parquet_format;
 pub mod datatypes;
 pub mod frame;
 pub mod dataframe;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod nulls;
\ No newline at end of file
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use arrow::datatypes::DataType as ArrowDataType;
+use polars::prelude::*;
+
+use crate::loaders::error::Result as FrameResult;
+
+
+/// Tokens that are treated as missing values when no configuration is given
+pub const DEFAULT_NULL_TOKENS: &[&str] = &["", "NA", "N/A", "null", "NULL", "-", "?", "0000-00-00"];
+
+/// Occurrences of each null token, per column
+pub type NullTokenCounts = BTreeMap<String, usize>;
+
+/// Values of a text column after null tokens have been replaced,
+/// re-typed when every remaining value is numeric
+enum Normalized<'a> {
+    Int(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+    Text(Vec<Option<&'a str>>),
+}
+
+/// Set of string tokens that encode a missing value
+/// Global tokens apply to every column, column tokens only to the named column
+///
+#[derive(Debug, Clone)]
+pub struct NullTokens {
+    global: HashSet<String>,
+    columns: HashMap<String, HashSet<String>>,
+}
+
+impl Default for NullTokens {
+    fn default() -> Self {
+        Self::new(DEFAULT_NULL_TOKENS.iter().map(|t| t.to_string()).collect())
+    }
+}
+
+impl NullTokens {
+    pub fn new(tokens: Vec<String>) -> Self {
+        NullTokens {
+            global: tokens.into_iter().collect(),
+            columns: HashMap::new(),
+        }
+    }
+
+    /// Add a global token
+    ///
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.global.insert(token.to_string());
+        self
+    }
+
+    /// Add tokens that are null only for column with name
+    ///
+    pub fn with_column_tokens(mut self, column: &str, tokens: Vec<String>) -> Self {
+        self.columns
+            .entry(column.to_string())
+            .or_insert_with(HashSet::new)
+            .extend(tokens);
+        self
+    }
+
+    pub fn is_null(&self, column: &str, value: &str) -> bool {
+        if self.global.contains(value) {
+            return true;
+        }
+        match self.columns.get(column) {
+            Some(tokens) => tokens.contains(value),
+            None => false,
+        }
+    }
+
+    /// Tokens of column that can be compared against numeric values
+    ///
+    fn numeric_tokens<T: FromStr>(&self, column: &str) -> Vec<T> {
+        self.global
+            .iter()
+            .chain(self.columns.get(column).into_iter().flatten())
+            .filter_map(|t| t.parse::<T>().ok())
+            .collect()
+    }
+
+    /// Replace null tokens with None and count how many times each token was seen
+    ///
+    fn scan<'a, I>(&self, column: &str, values: I) -> (Vec<Option<&'a str>>, NullTokenCounts)
+    where
+        I: Iterator<Item = Option<&'a str>>,
+    {
+        let mut counts: NullTokenCounts = BTreeMap::new();
+        let scanned = values
+            .map(|value| match value {
+                Some(v) if self.is_null(column, v) => {
+                    *counts.entry(v.to_string()).or_insert(0) += 1;
+                    None
+                }
+                other => other,
+            })
+            .collect();
+
+        (scanned, counts)
+    }
+
+    /// Replace numeric values equal to a token with None and count them by token
+    ///
+    fn scan_numeric<T, I>(values: I, tokens: &[T]) -> (Vec<Option<T>>, NullTokenCounts)
+    where
+        T: PartialEq + ToString,
+        I: Iterator<Item = Option<T>>,
+    {
+        let mut counts: NullTokenCounts = BTreeMap::new();
+        if tokens.is_empty() {
+            return (vec![], counts);
+        }
+        let scanned = values
+            .map(|value| match value {
+                Some(v) if tokens.contains(&v) => {
+                    *counts.entry(v.to_string()).or_insert(0) += 1;
+                    None
+                }
+                other => other,
+            })
+            .collect();
+
+        (scanned, counts)
+    }
+
+    /// Text columns that contained null tokens are often numeric columns in disguise
+    ///
+    fn retype<'a>(values: Vec<Option<&'a str>>) -> Normalized<'a> {
+        let present: Vec<&str> = values.iter().filter_map(|v| *v).collect();
+        if present.is_empty() {
+            return Normalized::Text(values);
+        }
+
+        if present.iter().all(|v| v.parse::<i64>().is_ok()) {
+            Normalized::Int(values.iter().map(|v| v.and_then(|v| v.parse().ok())).collect())
+        } else if present.iter().all(|v| v.parse::<f64>().is_ok()) {
+            Normalized::Float(values.iter().map(|v| v.and_then(|v| v.parse().ok())).collect())
+        } else {
+            Normalized::Text(values)
+        }
+    }
+
+    /// Return a copy of series with null tokens replaced by nulls
+    ///
+    pub fn normalize_series(&self, series: &Series) -> FrameResult<(Series, NullTokenCounts)> {
+        let name = series.name();
+
+        match series.dtype() {
+            ArrowDataType::Utf8 => {
+                let values = series.utf8()?;
+                let (scanned, counts) = self.scan(name, values.into_iter());
+                if counts.is_empty() {
+                    return Ok((series.clone(), counts));
+                }
+                let normalized = match Self::retype(scanned) {
+                    Normalized::Int(v) => Series::new(name, &v),
+                    Normalized::Float(v) => Series::new(name, &v),
+                    Normalized::Text(v) => Series::new(name, &v),
+                };
+                Ok((normalized, counts))
+            }
+
+            // integers are compared as integers, f64 cannot hold all of them
+            ArrowDataType::Int64 => {
+                let tokens: Vec<i64> = self.numeric_tokens(name);
+                let (values, counts) = Self::scan_numeric(series.i64()?.into_iter(), &tokens);
+                if counts.is_empty() {
+                    return Ok((series.clone(), counts));
+                }
+                Ok((Series::new(name, &values), counts))
+            }
+
+            ArrowDataType::Float64 => {
+                let tokens: Vec<f64> = self.numeric_tokens(name);
+                let (values, counts) = Self::scan_numeric(series.f64()?.into_iter(), &tokens);
+                if counts.is_empty() {
+                    return Ok((series.clone(), counts));
+                }
+                Ok((Series::new(name, &values), counts))
+            }
+
+            _ => Ok((series.clone(), BTreeMap::new())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_tokens_only_apply_to_their_column() {
+        let tokens = NullTokens::default().with_column_tokens("amount", vec!["0".to_string()]);
+        assert!(tokens.is_null("amount", "0"));
+        assert!(tokens.is_null("amount", "NA"));
+        assert!(!tokens.is_null("name", "0"));
+        assert!(tokens.is_null("name", ""));
+    }
+
+    #[test]
+    fn retype_numeric_text_after_tokens_are_removed() {
+        let tokens = NullTokens::default();
+        let values = vec![Some("1"), Some("NA"), Some("3"), Some("NA"), Some("?")];
+        let (scanned, counts) = tokens.scan("amount", values.into_iter());
+        assert_eq!(counts["NA"], 2);
+        assert_eq!(counts["?"], 1);
+        match NullTokens::retype(scanned) {
+            Normalized::Int(v) => assert_eq!(v, vec![Some(1), None, Some(3), None, None]),
+            _ => panic!("expected integer column"),
+        }
+    }
+
+    #[test]
+    fn integer_tokens_keep_large_integers() {
+        let tokens = NullTokens::default().with_column_tokens("id", vec!["-999".to_string()]);
+        let series = Series::new("id", &[Some(9_007_199_254_740_993i64), Some(-999), None]);
+        let (normalized, counts) = tokens.normalize_series(&series).unwrap();
+        assert_eq!(counts["-999"], 1);
+        let values: Vec<Option<i64>> = normalized.i64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(9_007_199_254_740_993), None, None]);
+    }
+}
@@ -0,0 +1,261 @@
+// Parquet files read as arrow record batches, one row group after the other
+
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
+use parquet::basic::LogicalType;
+use parquet::errors::ParquetError;
+use parquet::file::reader::{ChunkReader, FileReader, Length, SerializedFileReader};
+use parquet::file::statistics::Statistics;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::loaders::dataframe::ExactStatistics;
+use crate::loaders::error::{DataFrameError, Result};
+use crate::loaders::source::DataSource;
+
+/// Parquet file of a remote source read with ranged requests:
+/// the footer first, then only the column chunks needed
+///
+pub struct RangeChunkReader {
+    source: Arc<dyn DataSource>,
+    size: u64,
+}
+
+impl RangeChunkReader {
+    pub fn new(source: Arc<dyn DataSource>, size: u64) -> Self {
+        RangeChunkReader { source, size }
+    }
+}
+
+impl Length for RangeChunkReader {
+    fn len(&self) -> u64 {
+        self.size
+    }
+}
+
+impl ChunkReader for RangeChunkReader {
+    type T = Box<dyn Read>;
+
+    fn get_read(&self, start: u64, length: usize) -> parquet::errors::Result<Self::T> {
+        log::trace!("Reading {} bytes at {} of {}", length, start, self.source.uri());
+        self.source
+            .open_range(start, Some(length as u64))
+            .map_err(|e| ParquetError::General(e.to_string()))
+    }
+}
+
+pub struct ParquetReader<R>
+where R: ChunkReader + 'static
+{
+    reader: Arc<SerializedFileReader<R>>,
+    /// Columns to read, all if None
+    columns: Option<Vec<String>>,
+    batch_size: usize,
+}
+
+impl<R> ParquetReader<R>
+where R: ChunkReader + 'static
+{
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(ParquetReader {
+            reader: Arc::new(SerializedFileReader::new(reader)?),
+            columns: None,
+            batch_size: 1024,
+        })
+    }
+
+    /// Read only these columns (projection), the others are not decoded
+    ///
+    pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Number of rows from the file metadata, without reading data
+    ///
+    pub fn num_rows(&self) -> usize {
+        self.reader.metadata().file_metadata().num_rows() as usize
+    }
+
+    /// Index of the (leaf) columns to read
+    ///
+    fn projection(&self) -> Result<Vec<usize>> {
+        let descr = self.reader.metadata().file_metadata().schema_descr();
+        let names: Vec<String> = descr.columns().iter().map(|c| c.path().string()).collect();
+
+        match &self.columns {
+            None => Ok((0..names.len()).collect()),
+            Some(columns) => columns
+                .iter()
+                .map(|c| {
+                    names
+                        .iter()
+                        .position(|n| n == c)
+                        .ok_or_else(|| DataFrameError::ParseError(format!("No column {} in parquet file", c)))
+                })
+                .collect(),
+        }
+    }
+
+    /// Min and max of numeric columns, when every row group stores them
+    /// Only physical numeric types are used: min/max of byte arrays can be truncated by writers.
+    /// Unsigned and decimal columns are skipped (their statistics are ordered as signed, unscaled values),
+    /// as are Int64 values that an f64 cannot hold exactly
+    ///
+    pub fn statistics(&self) -> Result<HashMap<String, ExactStatistics>> {
+        let metadata = self.reader.metadata();
+        let mut result: HashMap<String, ExactStatistics> = HashMap::new();
+
+        for index in self.projection()? {
+            let descr = metadata.file_metadata().schema_descr().column(index);
+            let name = descr.path().string();
+            if !is_signed(descr.logical_type()) {
+                continue;
+            }
+            let mut exact: Option<ExactStatistics> = None;
+
+            for rg in metadata.row_groups() {
+                let (min, max) = match rg.column(index).statistics() {
+                    Some(stats) if stats.has_min_max_set() => match stats {
+                        Statistics::Int32(s) => (*s.min() as f64, *s.max() as f64),
+                        Statistics::Int64(s) if is_exact(*s.min()) && is_exact(*s.max()) => {
+                            (*s.min() as f64, *s.max() as f64)
+                        }
+                        Statistics::Float(s) => (*s.min() as f64, *s.max() as f64),
+                        Statistics::Double(s) => (*s.min(), *s.max()),
+                        _ => {
+                            exact = None;
+                            break;
+                        }
+                    },
+                    // a row group without statistics makes the whole column inexact
+                    _ => {
+                        exact = None;
+                        break;
+                    }
+                };
+                exact = Some(match exact {
+                    Some(e) => ExactStatistics { min: e.min.min(min), max: e.max.max(max) },
+                    None => ExactStatistics { min, max },
+                });
+            }
+
+            if let Some(e) = exact {
+                result.insert(name, e);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Decode the (projected) columns of every row group
+    ///
+    pub fn finish(self) -> Result<(Arc<Schema>, Vec<RecordBatch>)> {
+        let projection = self.projection()?;
+        let mut arrow_reader = ParquetFileArrowReader::new(self.reader.clone());
+        let schema = Arc::new(arrow_reader.get_schema_by_columns(projection.clone())?);
+        let batches = arrow_reader
+            .get_record_reader_by_columns(projection, self.batch_size)?
+            .collect::<std::result::Result<Vec<RecordBatch>, _>>()?;
+        Ok((schema, batches))
+    }
+}
+
+/// Logical types whose values are ordered like their signed physical type in statistics
+///
+fn is_signed(logical_type: LogicalType) -> bool {
+    matches!(
+        logical_type,
+        LogicalType::NONE
+            | LogicalType::INT_8
+            | LogicalType::INT_16
+            | LogicalType::INT_32
+            | LogicalType::INT_64
+            | LogicalType::DATE
+            | LogicalType::TIMESTAMP_MILLIS
+            | LogicalType::TIMESTAMP_MICROS
+    )
+}
+
+/// Integers up to 2^53 are exact as f64
+///
+fn is_exact(value: i64) -> bool {
+    (-(1 << 53)..=(1 << 53)).contains(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, ArrayRef, Int64Array, StringArray, TimestampMillisecondArray, UInt32Array};
+    use arrow::datatypes::{DataType, Field, TimeUnit};
+    use crate::loaders::source::FileSource;
+    use parquet::arrow::ArrowWriter;
+    use std::fs::File;
+
+    fn write_parquet(name: &str, batch: &RecordBatch) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(batch).unwrap();
+        writer.close().unwrap();
+        path
+    }
+
+    #[test]
+    fn statistics_of_signed_exact_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("at", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+            Field::new("count", DataType::UInt32, false),
+            Field::new("id", DataType::Int64, false),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from_vec(vec![1_600_000_000_000, 1_500_000_000_000], None)),
+            Arc::new(UInt32Array::from(vec![7, 1])),
+            Arc::new(Int64Array::from(vec![i64::MAX, 1])),
+        ];
+        let batch = RecordBatch::try_new(schema, columns).unwrap();
+        let path = write_parquet("xtract_parquet_statistics.parquet", &batch);
+
+        let statistics = ParquetReader::new(File::open(&path).unwrap()).unwrap().statistics().unwrap();
+        assert_eq!(
+            statistics.get("at"),
+            Some(&ExactStatistics { min: 1_500_000_000_000.0, max: 1_600_000_000_000.0 })
+        );
+        // unsigned values are stored as int32, ordered as signed in statistics
+        assert_eq!(statistics.get("count"), None);
+        assert_eq!(statistics.get("id"), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_projected_columns_with_ranges() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+        ];
+        let batch = RecordBatch::try_new(schema, columns).unwrap();
+        let path = write_parquet("xtract_parquet_ranges.parquet", &batch);
+        let size = std::fs::metadata(&path).unwrap().len();
+
+        let source = Arc::new(FileSource::new(path.to_str().unwrap()));
+        let reader = ParquetReader::new(RangeChunkReader::new(source, size))
+            .unwrap()
+            .with_columns(vec![String::from("name")]);
+        assert_eq!(reader.num_rows(), 3);
+        let (schema, batches) = reader.finish().unwrap();
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(schema.field(0).name(), "name");
+        let names = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!((names.value(0), names.is_null(1), names.value(2)), ("a", true, "c"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,11 +1,80 @@
 use rusoto_core::{HttpClient, Region};
-use rusoto_credential::StaticProvider;
-use rusoto_s3::{GetObjectRequest, S3Client, S3 };
-use tokio::io::AsyncReadExt;
+use rusoto_credential::{ChainProvider, StaticProvider};
+use once_cell::sync::Lazy;
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload, CompletedPart,
+    CreateMultipartUploadRequest, GetObjectOutput, GetObjectRequest, HeadObjectOutput, HeadObjectRequest,
+    ListObjectsV2Request, Object, PutObjectRequest, S3Client, UploadPartRequest, S3,
+};
+use std::env::var;
+use std::future::Future;
+use std::io::{self, Read};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::runtime::Runtime;
+
+use crate::configuration::StorageProfile;
+use crate::loaders::error::{DataFrameError, Result};
 // use std::str;
 // use std::error::Error;
 
 
+/// Runtime driving the async s3 client from blocking code
+static RT: Lazy<std::io::Result<Runtime>> = Lazy::new(Runtime::new);
+
+/// Run a request of the s3 client to completion from blocking code
+///
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    match &*RT {
+        Ok(runtime) => Ok(runtime.handle().block_on(future)),
+        Err(e) => Err(DataFrameError::StorageError(format!("Cannot start the s3 client runtime: {}", e))),
+    }
+}
+
+/// Size of the parts of multipart uploads, content up to this size is sent in a single PUT
+/// (parts must be at least 5 MiB)
+const PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Parts of a multipart upload, PART_SIZE bytes each but the last,
+/// None when content fits in a single PUT
+///
+fn split_parts(content: &[u8]) -> Option<Vec<&[u8]>> {
+    if content.len() <= PART_SIZE {
+        None
+    } else {
+        Some(content.chunks(PART_SIZE).collect())
+    }
+}
+
+/// Object tags as the x-amz-tagging header expects them, key1=value1&key2=value2 url encoded
+///
+pub fn tagging(tags: &[(String, String)]) -> String {
+    let encode = |s: &str| {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect::<String>()
+    };
+    tags.iter()
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Split s3://bucket/path/to/key into bucket and key
+///
+pub fn parse_uri(uri: &str) -> Result<(String, String)> {
+    let path = uri
+        .strip_prefix("s3://")
+        .ok_or_else(|| DataFrameError::ParseError(format!("Not an s3 uri: {}", uri)))?;
+    match path.find('/') {
+        Some(i) if i > 0 => Ok((path[..i].to_string(), path[i + 1..].to_string())),
+        _ => Err(DataFrameError::ParseError(format!("Expected s3://bucket/key, got {}", uri))),
+    }
+}
+
+#[derive(Clone)]
 pub struct Storage {
     name: String,
     client: S3Client,
@@ -16,52 +85,244 @@ pub struct Storage {
 
 impl Storage {
 
-    pub fn new() -> Self {
+    /// Client for a bucket, with the settings of profile completed from the environment
+    ///
+    pub fn new(bucket: &str, profile: &StorageProfile) -> Result<Self> {
+        let region = match &profile.region {
+            Some(name) => name
+                .parse::<Region>()
+                .map_err(|e| DataFrameError::StorageError(format!("{} ({})", e, name)))?,
+            // AWS_DEFAULT_REGION or AWS_REGION, us-east-1 otherwise
+            None => Region::default(),
+        };
+        let endpoint = profile
+            .url
+            .clone()
+            .or_else(|| var("AWS_ENDPOINT_URL_S3").ok())
+            .or_else(|| var("AWS_ENDPOINT_URL").ok());
+        let region = match endpoint {
+            Some(endpoint) => Region::Custom {
+                name: region.name().to_string(),
+                endpoint,
+            },
+            None => region,
+        };
 
-        let region = Region::Custom {
-            name: "us-east-1".into(),
-            endpoint: "http://localhost:9000".into(),
+        let dispatcher = HttpClient::new().map_err(|e| DataFrameError::StorageError(e.to_string()))?;
+        let client = match (&profile.access_key, &profile.secret_access_key) {
+            (Some(key), Some(secret)) => S3Client::new_with(
+                dispatcher,
+                StaticProvider::new(key.to_owned(), secret.to_owned(), None, None),
+                region.clone(),
+            ),
+            // environment, ~/.aws/credentials (AWS_PROFILE), container or instance role
+            _ => S3Client::new_with(dispatcher, ChainProvider::new(), region.clone()),
         };
+        log::debug!("Bucket {} at {:?}", bucket, region);
 
-        let bucket = "frankiebucket".to_string();
+        Ok(Storage {
+            name: region.name().to_string(),
+            client,
+            bucket: bucket.to_string(),
+        })
+    }
 
-        let s3_client = S3Client::new_with(
-            HttpClient::new().expect("failed to create request dispatcher"),
-            StaticProvider::new(
-                "frag".to_owned(),
-                "supersecretkey".to_owned(),
-                None,
-                None,
-            ),
-            region,
-        );
+    /// Objects with keys starting with prefix, all pages of the listing
+    ///
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<Object>> {
+        let mut objects: Vec<Object> = vec![];
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let list_req = ListObjectsV2Request {
+                bucket: self.bucket.to_owned(),
+                prefix: Some(prefix.to_owned()),
+                continuation_token: continuation_token.clone(),
+                ..Default::default()
+            };
+            let page = self
+                .client
+                .list_objects_v2(list_req)
+                .await
+                .map_err(|e| DataFrameError::StorageError(format!("Could not list {}/{}: {}", self.bucket, prefix, e)))?;
+            objects.extend(page.contents.unwrap_or_default());
+            continuation_token = page.next_continuation_token;
+            if page.is_truncated != Some(true) || continuation_token.is_none() {
+                break;
+            }
+        }
+        log::debug!("{} object(s) under {}/{}", objects.len(), self.bucket, prefix);
+        Ok(objects)
+    }
+
+    /// Write content as an object, in parts when larger than PART_SIZE
+    ///
+    pub async fn put_object(&self, key: &str, content: &[u8], content_type: &str, tagging: Option<String>) -> Result<()> {
+        let storage_error = |e: String| DataFrameError::StorageError(format!("Could not PUT {}/{}: {}", self.bucket, key, e));
+        let parts = match split_parts(content) {
+            Some(parts) => parts,
+            None => {
+                let put_req = PutObjectRequest {
+                    bucket: self.bucket.to_owned(),
+                    key: key.to_owned(),
+                    body: Some(content.to_vec().into()),
+                    content_length: Some(content.len() as i64),
+                    content_type: Some(content_type.to_owned()),
+                    tagging,
+                    ..Default::default()
+                };
+                self.client.put_object(put_req).await.map_err(|e| storage_error(e.to_string()))?;
+                log::debug!("PUT {} bytes to {}/{}", content.len(), self.bucket, key);
+                return Ok(());
+            }
+        };
+
+        let create_req = CreateMultipartUploadRequest {
+            bucket: self.bucket.to_owned(),
+            key: key.to_owned(),
+            content_type: Some(content_type.to_owned()),
+            tagging,
+            ..Default::default()
+        };
+        let upload_id = self
+            .client
+            .create_multipart_upload(create_req)
+            .await
+            .map_err(|e| storage_error(e.to_string()))?
+            .upload_id
+            .ok_or_else(|| storage_error(String::from("no upload id")))?;
 
-        Storage {
-            name: "minio".into(),
-            client: s3_client,
-            bucket,
+        let mut completed: Vec<CompletedPart> = vec![];
+        for (i, chunk) in parts.into_iter().enumerate() {
+            let part_req = UploadPartRequest {
+                bucket: self.bucket.to_owned(),
+                key: key.to_owned(),
+                upload_id: upload_id.clone(),
+                part_number: i as i64 + 1,
+                body: Some(chunk.to_vec().into()),
+                content_length: Some(chunk.len() as i64),
+                ..Default::default()
+            };
+            match self.client.upload_part(part_req).await {
+                Ok(part) => completed.push(CompletedPart {
+                    e_tag: part.e_tag,
+                    part_number: Some(i as i64 + 1),
+                }),
+                Err(e) => {
+                    // parts already sent are kept (and billed) until the upload is aborted
+                    let abort_req = AbortMultipartUploadRequest {
+                        bucket: self.bucket.to_owned(),
+                        key: key.to_owned(),
+                        upload_id: upload_id.clone(),
+                        ..Default::default()
+                    };
+                    if let Err(abort) = self.client.abort_multipart_upload(abort_req).await {
+                        log::warn!("Could not abort upload {} of {}/{}: {}", upload_id, self.bucket, key, abort);
+                    }
+                    return Err(storage_error(e.to_string()));
+                }
+            }
         }
 
+        let complete_req = CompleteMultipartUploadRequest {
+            bucket: self.bucket.to_owned(),
+            key: key.to_owned(),
+            upload_id,
+            multipart_upload: Some(CompletedMultipartUpload { parts: Some(completed) }),
+            ..Default::default()
+        };
+        self.client
+            .complete_multipart_upload(complete_req)
+            .await
+            .map_err(|e| storage_error(e.to_string()))?;
+        log::debug!("PUT {} bytes to {}/{} in parts", content.len(), self.bucket, key);
+        Ok(())
     }
 
-    pub async fn get_object(&self, filename: String) -> (Vec<u8>, String) {
+    pub async fn head_object(&self, key: &str) -> Result<HeadObjectOutput> {
+        let head_req = HeadObjectRequest {
+            bucket: self.bucket.to_owned(),
+            key: key.to_owned(),
+            ..Default::default()
+        };
+
+        self.client
+            .head_object(head_req)
+            .await
+            .map_err(|e| DataFrameError::StorageError(format!("Could not HEAD {}/{}: {}", self.bucket, key, e)))
+    }
+
+    /// Object as a stream, or only length bytes from start with a ranged GET
+    /// The body is read from the network as the reader is consumed
+    ///
+    pub async fn get_object(&self, key: &str, start: u64, length: Option<u64>) -> Result<(ObjectReader, GetObjectOutput)> {
+        let range = match (start, length) {
+            (0, None) => None,
+            (start, None) => Some(format!("bytes={}-", start)),
+            (start, Some(length)) => Some(format!("bytes={}-{}", start, start + length.max(1) - 1)),
+        };
         let get_req = GetObjectRequest {
             bucket: self.bucket.to_owned(),
-            key: filename.to_owned(),
+            key: key.to_owned(),
+            range,
             ..Default::default()
         };
 
-        let data = self.client.get_object(get_req).await.expect("Could not GET remote file! :(( ");
-        dbg!(&data);
-        let content_type = data.content_type.unwrap();
-        let mut stream = data.body.unwrap().into_async_read();
-        let mut body = Vec::new();
-        stream.read_to_end(&mut body).await.unwrap();
+        let mut data = self
+            .client
+            .get_object(get_req)
+            .await
+            .map_err(|e| DataFrameError::StorageError(format!("Could not GET {}/{}: {}", self.bucket, key, e)))?;
+        log::debug!("GET {} from bucket {}: {:?}", key, self.bucket, data);
+        let body = data
+            .body
+            .take()
+            .ok_or_else(|| DataFrameError::StorageError(format!("No body for {}/{}", self.bucket, key)))?;
+        let reader = ObjectReader {
+            body: Box::new(body.into_async_read()),
+        };
+        Ok((reader, data))
+    }
+}
+
+/// Blocking reader over the body of an object
+///
+pub struct ObjectReader {
+    body: Box<dyn AsyncRead + Send + Sync + Unpin>,
+}
+
+impl Read for ObjectReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        block_on(self.body.read(buf)).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (body, content_type)
-        // println!("remote data {:?}", data_str);
+    #[test]
+    fn parse_bucket_and_key() {
+        assert_eq!(
+            parse_uri("s3://landing/2020/12/01/data.csv").unwrap(),
+            (String::from("landing"), String::from("2020/12/01/data.csv"))
+        );
+        assert!(parse_uri("s3://data.csv").is_err());
+        assert!(parse_uri("s3:///data.csv").is_err());
+        let tags = vec![(String::from("xtract-source"), String::from("s3://landing/a b.csv"))];
+        assert_eq!(tagging(&tags), "xtract-source=s3%3A%2F%2Flanding%2Fa%20b.csv");
     }
 
+    #[test]
+    fn split_parts_around_part_size() {
+        assert!(split_parts(&[]).is_none());
+        assert!(split_parts(&vec![0u8; PART_SIZE]).is_none());
 
-}
\ No newline at end of file
+        let content = vec![0u8; PART_SIZE + 1];
+        let parts = split_parts(&content).unwrap();
+        assert_eq!(parts.iter().map(|p| p.len()).collect::<Vec<usize>>(), vec![PART_SIZE, 1]);
+
+        let content = vec![0u8; 2 * PART_SIZE];
+        let parts = split_parts(&content).unwrap();
+        assert_eq!(parts.iter().map(|p| p.len()).collect::<Vec<usize>>(), vec![PART_SIZE, PART_SIZE]);
+    }
+}
@@ -0,0 +1,104 @@
+// Where artifacts (profiles, reports, transformed data) are written: local files or buckets
+
+use std::fs::File;
+use std::io::Write;
+
+use crate::loaders::error::Result;
+use crate::loaders::s3_connector::{self, block_on, Storage};
+use crate::loaders::source::SourceMeta;
+
+/// Tags linking an artifact to the data it was made from
+///
+pub fn source_tags(source: &SourceMeta) -> Vec<(String, String)> {
+    vec![
+        (String::from("xtract-source"), source.uri.clone()),
+        (String::from("xtract-fingerprint"), source.fingerprint()),
+    ]
+}
+
+pub trait DataSink {
+    fn uri(&self) -> &str;
+
+    /// Write the whole content, replacing what was there
+    /// Tags are kept by sinks supporting them (object tags) and ignored otherwise
+    fn put(&self, content: &[u8], content_type: &str, tags: &[(String, String)]) -> Result<()>;
+}
+
+pub struct FileSink {
+    path: String,
+}
+
+impl FileSink {
+    pub fn new(uri: &str) -> Self {
+        FileSink {
+            path: uri.trim_start_matches("file://").to_string(),
+        }
+    }
+}
+
+impl DataSink for FileSink {
+    fn uri(&self) -> &str {
+        &self.path
+    }
+
+    fn put(&self, content: &[u8], _content_type: &str, _tags: &[(String, String)]) -> Result<()> {
+        File::create(&self.path)?.write_all(content)?;
+        Ok(())
+    }
+}
+
+/// Object in a bucket, as s3://bucket/key
+/// Large content is sent as a multipart upload
+///
+pub struct S3Sink {
+    uri: String,
+    key: String,
+    storage: Storage,
+}
+
+impl S3Sink {
+    pub fn new(uri: &str, storage: Storage) -> Result<Self> {
+        let (_, key) = s3_connector::parse_uri(uri)?;
+        Ok(S3Sink {
+            uri: uri.to_string(),
+            key,
+            storage,
+        })
+    }
+}
+
+impl DataSink for S3Sink {
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn put(&self, content: &[u8], content_type: &str, tags: &[(String, String)]) -> Result<()> {
+        let tagging = if tags.is_empty() { None } else { Some(s3_connector::tagging(tags)) };
+        block_on(self.storage.put_object(&self.key, content, content_type, tagging))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaders::source::Registry;
+
+    #[test]
+    fn create_sink_from_uri() {
+        let registry = Registry::default();
+        assert_eq!(registry.create("out/profile.json").unwrap().uri(), "out/profile.json");
+        assert_eq!(registry.create("file:///tmp/profile.json").unwrap().uri(), "/tmp/profile.json");
+        assert!(registry.create("ftp://host/profile.json").is_err());
+    }
+
+    #[test]
+    fn put_replaces_file_content() {
+        let path = std::env::temp_dir().join("xtract_file_sink.json");
+        let sink = FileSink::new(&format!("file://{}", path.to_str().unwrap()));
+        let tags = vec![(String::from("xtract-source"), String::from("data.csv"))];
+        sink.put(b"{\"a\": 1, \"b\": 2}", "application/json", &tags).unwrap();
+        sink.put(b"{}", "application/json", &[]).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
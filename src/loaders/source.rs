@@ -0,0 +1,678 @@
+// Where input data comes from: local files, stdin, buckets, web servers, ...
+// A registry maps the scheme of a uri to the DataSource reading it
+
+use flate2::read::MultiGzDecoder;
+use glob::Pattern;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderName, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, ETAG, LAST_MODIFIED, RANGE};
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::configuration::{self, HttpAuth};
+use crate::loaders::error::{DataFrameError, Result};
+use crate::loaders::s3_connector::{self, block_on, Storage};
+use crate::loaders::sink::{DataSink, FileSink, S3Sink};
+
+/// What is known about a source before reading it
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceMeta {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// As an http date, e.g. Wed, 21 Oct 2020 07:28:00 GMT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl SourceMeta {
+    /// Identifies this version of the data: the etag when known,
+    /// otherwise a hash of the uri, size and last modified time
+    ///
+    pub fn fingerprint(&self) -> String {
+        match &self.etag {
+            Some(etag) => etag.trim_matches('"').to_string(),
+            None => {
+                let mut hasher = DefaultHasher::new();
+                self.uri.hash(&mut hasher);
+                self.size.hash(&mut hasher);
+                self.last_modified.hash(&mut hasher);
+                format!("{:016x}", hasher.finish())
+            }
+        }
+    }
+}
+
+pub trait DataSource {
+    /// Uri as given by the user
+    fn uri(&self) -> &str;
+
+    /// Kind of location (local, stdin, s3, ...)
+    fn kind(&self) -> &'static str;
+
+    fn meta(&self) -> Result<SourceMeta>;
+
+    /// Reader over the whole content, not seekable in general, and what is known about it once opened
+    fn open(&self) -> Result<(Box<dyn Read>, SourceMeta)>;
+
+    /// Whether open_range reads only the bytes asked for (e.g. with ranged requests)
+    fn ranged(&self) -> bool {
+        false
+    }
+
+    /// Reader over length bytes from start (up to the end if None), e.g. a parquet footer
+    fn open_range(&self, start: u64, length: Option<u64>) -> Result<Box<dyn Read>> {
+        let (mut reader, _) = self.open()?;
+        io::copy(&mut (&mut reader).take(start), &mut io::sink())?;
+        Ok(match length {
+            Some(length) => Box::new(reader.take(length)),
+            None => reader,
+        })
+    }
+
+    /// Objects under this uri when it is a prefix (s3://bucket/drops/2020-12-01/)
+    fn list(&self) -> Result<Vec<SourceMeta>> {
+        Err(DataFrameError::IoError(format!("Cannot list {}", self.uri())))
+    }
+
+    /// Path of the content when it is a local file, readers may then seek
+    fn local_path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Last segment of the uri, used to detect format and compression (data.csv.gz)
+    fn name(&self) -> &str {
+        let uri = self.uri().split(&['?', '#'][..]).next().unwrap_or("");
+        uri.rsplit('/').next().unwrap_or(uri)
+    }
+}
+
+pub struct FileSource {
+    uri: String,
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(uri: &str) -> Self {
+        FileSource {
+            uri: uri.to_string(),
+            path: PathBuf::from(uri.trim_start_matches("file://")),
+        }
+    }
+}
+
+impl DataSource for FileSource {
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn kind(&self) -> &'static str {
+        "local"
+    }
+
+    fn meta(&self) -> Result<SourceMeta> {
+        let metadata = std::fs::metadata(&self.path)?;
+        Ok(SourceMeta {
+            uri: self.uri.clone(),
+            size: Some(metadata.len()),
+            last_modified: metadata.modified().ok().map(httpdate::fmt_http_date),
+            ..Default::default()
+        })
+    }
+
+    fn open(&self) -> Result<(Box<dyn Read>, SourceMeta)> {
+        Ok((Box::new(File::open(&self.path)?), self.meta()?))
+    }
+
+    fn ranged(&self) -> bool {
+        true
+    }
+
+    fn open_range(&self, start: u64, length: Option<u64>) -> Result<Box<dyn Read>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(start))?;
+        Ok(match length {
+            Some(length) => Box::new(file.take(length)),
+            None => Box::new(file),
+        })
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
+
+pub struct StdinSource;
+
+impl DataSource for StdinSource {
+    fn uri(&self) -> &str {
+        "-"
+    }
+
+    fn kind(&self) -> &'static str {
+        "stdin"
+    }
+
+    fn meta(&self) -> Result<SourceMeta> {
+        Ok(SourceMeta {
+            uri: String::from("-"),
+            ..Default::default()
+        })
+    }
+
+    fn open(&self) -> Result<(Box<dyn Read>, SourceMeta)> {
+        Ok((Box::new(std::io::stdin()), self.meta()?))
+    }
+
+    fn name(&self) -> &str {
+        ""
+    }
+}
+
+/// Object in a bucket, as s3://bucket/key
+///
+pub struct S3Source {
+    uri: String,
+    bucket: String,
+    key: String,
+    storage: Storage,
+}
+
+impl S3Source {
+    pub fn new(uri: &str, storage: &configuration::Storage, profile: Option<&str>) -> Result<Self> {
+        let (bucket, key) = s3_connector::parse_uri(uri)?;
+        Ok(S3Source {
+            uri: uri.to_string(),
+            storage: bucket_storage(&bucket, storage, profile)?,
+            bucket,
+            key,
+        })
+    }
+}
+
+/// Client for a bucket with the storage profile given, or the one of the bucket
+///
+fn bucket_storage(bucket: &str, storage: &configuration::Storage, profile: Option<&str>) -> Result<Storage> {
+    let settings = storage
+        .profile(profile, bucket)
+        .ok_or_else(|| DataFrameError::StorageError(format!("No storage profile {:?} in config", profile)))?;
+    Storage::new(bucket, settings)
+}
+
+impl DataSource for S3Source {
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn kind(&self) -> &'static str {
+        "s3"
+    }
+
+    fn meta(&self) -> Result<SourceMeta> {
+        let head = block_on(self.storage.head_object(&self.key))??;
+        Ok(SourceMeta {
+            uri: self.uri.clone(),
+            size: head.content_length.map(|l| l as u64),
+            content_type: head.content_type,
+            last_modified: head.last_modified,
+            etag: head.e_tag,
+        })
+    }
+
+    fn open(&self) -> Result<(Box<dyn Read>, SourceMeta)> {
+        let (body, output) = block_on(self.storage.get_object(&self.key, 0, None))??;
+        let meta = SourceMeta {
+            uri: self.uri.clone(),
+            size: output.content_length.map(|l| l as u64),
+            content_type: output.content_type,
+            last_modified: output.last_modified,
+            etag: output.e_tag,
+        };
+        Ok((Box::new(body), meta))
+    }
+
+    fn ranged(&self) -> bool {
+        true
+    }
+
+    fn open_range(&self, start: u64, length: Option<u64>) -> Result<Box<dyn Read>> {
+        let (body, _) = block_on(self.storage.get_object(&self.key, start, length))??;
+        Ok(Box::new(body))
+    }
+
+    fn list(&self) -> Result<Vec<SourceMeta>> {
+        let objects = block_on(self.storage.list_objects(&self.key))??;
+        Ok(objects
+            .into_iter()
+            .filter_map(|object| {
+                let key = object.key?;
+                // folder placeholders created by consoles
+                if key.ends_with('/') {
+                    return None;
+                }
+                Some(SourceMeta {
+                    uri: format!("s3://{}/{}", self.bucket, key),
+                    size: object.size.map(|s| s as u64),
+                    last_modified: object.last_modified,
+                    etag: object.e_tag,
+                    ..Default::default()
+                })
+            })
+            .collect())
+    }
+}
+
+/// File served over http(s), e.g. by an open data portal
+/// The body is streamed, gzip content encoding is decoded on the fly
+///
+pub struct HttpSource {
+    uri: String,
+    auth: Option<HttpAuth>,
+}
+
+impl HttpSource {
+    pub fn new(uri: &str, auth: Option<HttpAuth>) -> Self {
+        HttpSource {
+            uri: uri.to_string(),
+            auth,
+        }
+    }
+
+    fn request(&self, method: Method, range: Option<String>) -> Result<Response> {
+        let client = Client::builder().build().map_err(http_error)?;
+        let request = client.request(method, &self.uri).header(ACCEPT_ENCODING, "gzip, identity");
+        let request = match range {
+            Some(range) => request.header(RANGE, range),
+            None => request,
+        };
+        let request = match &self.auth {
+            Some(HttpAuth { token: Some(token), .. }) => request.bearer_auth(token),
+            Some(HttpAuth { username: Some(username), password, .. }) => request.basic_auth(username, password.as_ref()),
+            _ => request,
+        };
+        let response = request.send().map_err(http_error)?;
+        response.error_for_status().map_err(http_error)
+    }
+
+    fn response_meta(&self, response: &Response) -> SourceMeta {
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        SourceMeta {
+            uri: self.uri.clone(),
+            size: response.content_length(),
+            content_type: header(CONTENT_TYPE),
+            last_modified: header(LAST_MODIFIED),
+            etag: header(ETAG),
+        }
+    }
+}
+
+fn http_error(error: reqwest::Error) -> DataFrameError {
+    DataFrameError::IoError(error.to_string())
+}
+
+impl DataSource for HttpSource {
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn kind(&self) -> &'static str {
+        "http"
+    }
+
+    fn meta(&self) -> Result<SourceMeta> {
+        Ok(self.response_meta(&self.request(Method::HEAD, None)?))
+    }
+
+    fn open(&self) -> Result<(Box<dyn Read>, SourceMeta)> {
+        let response = self.request(Method::GET, None)?;
+        let meta = self.response_meta(&response);
+        let encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_lowercase());
+        let reader: Box<dyn Read> = match encoding.as_deref() {
+            None | Some("identity") => Box::new(response),
+            Some("gzip") | Some("x-gzip") => Box::new(MultiGzDecoder::new(response)),
+            Some(other) => {
+                return Err(DataFrameError::IoError(format!(
+                    "Unsupported content encoding {} for {}",
+                    other, self.uri
+                )))
+            }
+        };
+        Ok((reader, meta))
+    }
+
+    fn ranged(&self) -> bool {
+        true
+    }
+
+    fn open_range(&self, start: u64, length: Option<u64>) -> Result<Box<dyn Read>> {
+        let range = match length {
+            Some(length) => format!("bytes={}-{}", start, start + length.max(1) - 1),
+            None => format!("bytes={}-", start),
+        };
+        let mut response = self.request(Method::GET, Some(range))?;
+        // servers ignoring the range send the whole content
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            io::copy(&mut (&mut response).take(start), &mut io::sink())?;
+        }
+        Ok(match length {
+            Some(length) => Box::new(response.take(length)),
+            None => Box::new(response),
+        })
+    }
+}
+
+/// Which listed objects to read: glob on the key below the prefix, suffix of the key,
+/// last modified at or after a date (2020-12-01) or time (2020-12-01T06:00:00)
+///
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    glob: Option<Pattern>,
+    suffix: Option<String>,
+    modified_since: Option<String>,
+}
+
+impl ListFilter {
+    pub fn with_glob(mut self, glob: &str) -> Result<Self> {
+        let pattern = Pattern::new(glob).map_err(|e| DataFrameError::ParseError(format!("{} ({})", e, glob)))?;
+        self.glob = Some(pattern);
+        Ok(self)
+    }
+
+    pub fn with_suffix(mut self, suffix: &str) -> Self {
+        self.suffix = Some(suffix.to_string());
+        self
+    }
+
+    pub fn with_modified_since(mut self, since: &str) -> Result<Self> {
+        let valid = since.len() >= 10
+            && since.chars().enumerate().take(10).all(|(i, c)| match i {
+                4 | 7 => c == '-',
+                _ => c.is_ascii_digit(),
+            });
+        if !valid {
+            return Err(DataFrameError::ParseError(format!(
+                "Expected a date as 2020-12-01 or 2020-12-01T06:00:00, got {}",
+                since
+            )));
+        }
+        self.modified_since = Some(since.to_string());
+        Ok(self)
+    }
+
+    /// Whether an object listed under prefix passes all filters
+    /// Listings give times in ISO 8601 (2020-12-01T06:12:00.000Z), compared as text
+    ///
+    pub fn matches(&self, prefix: &str, object: &SourceMeta) -> bool {
+        let relative = object.uri.strip_prefix(prefix).unwrap_or(&object.uri);
+        if let Some(glob) = &self.glob {
+            if !glob.matches(relative) {
+                return false;
+            }
+        }
+        if let Some(suffix) = &self.suffix {
+            if !relative.ends_with(suffix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(since) = &self.modified_since {
+            match &object.last_modified {
+                Some(modified) if modified.as_str() >= since.as_str() => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+pub type SourceFactory = Box<dyn Fn(&str) -> Result<Box<dyn DataSource>>>;
+pub type SinkFactory = Box<dyn Fn(&str) -> Result<Box<dyn DataSink>>>;
+
+fn file_source(uri: &str) -> Result<Box<dyn DataSource>> {
+    Ok(Box::new(FileSource::new(uri)))
+}
+
+/// S3 sources with the settings of the storage profile, or of their bucket
+///
+fn s3_factory(storage: configuration::Storage, profile: Option<String>) -> SourceFactory {
+    Box::new(move |uri: &str| -> Result<Box<dyn DataSource>> {
+        Ok(Box::new(S3Source::new(uri, &storage, profile.as_deref())?))
+    })
+}
+
+fn s3_sink_factory(storage: configuration::Storage, profile: Option<String>) -> SinkFactory {
+    Box::new(move |uri: &str| -> Result<Box<dyn DataSink>> {
+        let (bucket, _) = s3_connector::parse_uri(uri)?;
+        Ok(Box::new(S3Sink::new(uri, bucket_storage(&bucket, &storage, profile.as_deref())?)?))
+    })
+}
+
+/// Http(s) sources sending the credentials configured for their host
+///
+fn http_factory(hosts: HashMap<String, HttpAuth>) -> SourceFactory {
+    Box::new(move |uri: &str| -> Result<Box<dyn DataSource>> {
+        let host = Url::parse(uri)
+            .map_err(|e| DataFrameError::ParseError(format!("{} ({})", e, uri)))?
+            .host_str()
+            .map(String::from);
+        let auth = host.and_then(|host| hosts.get(&host).cloned());
+        Ok(Box::new(HttpSource::new(uri, auth)))
+    })
+}
+
+/// Data sources (and sinks) by uri scheme, paths without a scheme are local files and - is stdin
+///
+pub struct Registry {
+    schemes: HashMap<String, SourceFactory>,
+    sinks: HashMap<String, SinkFactory>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry {
+            schemes: HashMap::new(),
+            sinks: HashMap::new(),
+        }
+        .with_scheme("file", Box::new(file_source))
+        .with_storage(configuration::Storage::default(), None)
+        .with_http(HashMap::new())
+    }
+}
+
+impl Registry {
+    /// Read uris starting with scheme:// with factory, replacing any previous one
+    ///
+    pub fn with_scheme(mut self, scheme: &str, factory: SourceFactory) -> Self {
+        self.schemes.insert(scheme.to_lowercase(), factory);
+        self
+    }
+
+    /// Write uris starting with scheme:// with factory, replacing any previous one
+    ///
+    pub fn with_sink_scheme(mut self, scheme: &str, factory: SinkFactory) -> Self {
+        self.sinks.insert(scheme.to_lowercase(), factory);
+        self
+    }
+
+    /// Read and write s3 uris with the storage settings from config, profile picks a named one
+    ///
+    pub fn with_storage(self, storage: configuration::Storage, profile: Option<String>) -> Self {
+        self.with_scheme("s3", s3_factory(storage.clone(), profile.clone()))
+            .with_sink_scheme("s3", s3_sink_factory(storage, profile))
+    }
+
+    /// Read http and https uris with the credentials of each host
+    ///
+    pub fn with_http(self, hosts: HashMap<String, HttpAuth>) -> Self {
+        self.with_scheme("http", http_factory(hosts.clone()))
+            .with_scheme("https", http_factory(hosts))
+    }
+
+    /// Objects under a prefix passing filter, sorted by uri
+    ///
+    pub fn list(&self, prefix: &str, filter: &ListFilter) -> Result<Vec<SourceMeta>> {
+        let mut objects: Vec<SourceMeta> = self
+            .open(prefix)?
+            .list()?
+            .into_iter()
+            .filter(|object| filter.matches(prefix, object))
+            .collect();
+        objects.sort_by(|a, b| a.uri.cmp(&b.uri));
+        Ok(objects)
+    }
+
+    /// Sink writing to uri, a local file without a scheme
+    ///
+    pub fn create(&self, uri: &str) -> Result<Box<dyn DataSink>> {
+        match uri.find("://") {
+            Some(i) if &uri[..i] != "file" => {
+                let scheme = uri[..i].to_lowercase();
+                let factory = self
+                    .sinks
+                    .get(&scheme)
+                    .ok_or_else(|| DataFrameError::IoError(format!("Cannot write to {}:// ({})", scheme, uri)))?;
+                factory(uri)
+            }
+            _ => Ok(Box::new(FileSink::new(uri))),
+        }
+    }
+
+    pub fn open(&self, uri: &str) -> Result<Box<dyn DataSource>> {
+        if uri == "-" {
+            return Ok(Box::new(StdinSource));
+        }
+        match uri.find("://") {
+            Some(i) => {
+                let scheme = uri[..i].to_lowercase();
+                let factory = self
+                    .schemes
+                    .get(&scheme)
+                    .ok_or_else(|| DataFrameError::IoError(format!("No data source for {}:// ({})", scheme, uri)))?;
+                factory(uri)
+            }
+            None => Ok(Box::new(FileSource::new(uri))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch_on_scheme() {
+        let registry = Registry::default();
+        assert_eq!(registry.open("data/file.csv").unwrap().kind(), "local");
+        assert_eq!(registry.open("file:///tmp/file.csv.gz").unwrap().name(), "file.csv.gz");
+        assert_eq!(registry.open("-").unwrap().kind(), "stdin");
+        assert!(registry.open("ftp://host/file.csv").is_err());
+        assert_eq!(registry.open("https://host/data/file.csv?v=2").unwrap().name(), "file.csv");
+    }
+
+    #[test]
+    fn open_file_range() {
+        let path = std::env::temp_dir().join("xtract_open_range.csv");
+        std::fs::write(&path, "id,name\n1,a\n2,b\n").unwrap();
+        let source = FileSource::new(path.to_str().unwrap());
+
+        let mut range = String::new();
+        source.open_range(8, Some(4)).unwrap().read_to_string(&mut range).unwrap();
+        assert_eq!(range, "1,a\n");
+        let mut tail = String::new();
+        source.open_range(12, None).unwrap().read_to_string(&mut tail).unwrap();
+        assert_eq!(tail, "2,b\n");
+        // past the end is empty, not an error
+        let mut past = String::new();
+        source.open_range(100, Some(4)).unwrap().read_to_string(&mut past).unwrap();
+        assert!(past.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn filter_listed_objects() {
+        let object = |key: &str, modified: &str| SourceMeta {
+            uri: format!("s3://landing/drops/{}", key),
+            last_modified: Some(modified.to_string()),
+            ..Default::default()
+        };
+        let filter = ListFilter::default()
+            .with_glob("2020-12-*/*.csv")
+            .unwrap()
+            .with_modified_since("2020-12-02")
+            .unwrap();
+        let prefix = "s3://landing/drops/";
+        assert!(filter.matches(prefix, &object("2020-12-02/cards.csv", "2020-12-02T06:00:00.000Z")));
+        assert!(!filter.matches(prefix, &object("2020-12-01/cards.csv", "2020-12-01T06:00:00.000Z")));
+        assert!(!filter.matches(prefix, &object("2020-12-02/cards.json", "2020-12-02T06:00:00.000Z")));
+        assert!(ListFilter::default().with_modified_since("yesterday").is_err());
+    }
+
+    #[test]
+    fn stream_http_body_with_metadata() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push(line);
+            }
+            let body = "id,name\n1,a\n";
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        let auth = HttpAuth {
+            token: Some(String::from("secret")),
+            ..Default::default()
+        };
+        let mut hosts = HashMap::new();
+        hosts.insert(String::from("127.0.0.1"), auth);
+        let registry = Registry::default().with_http(hosts);
+        let source = registry.open(&format!("http://{}/drops/data.csv", address)).unwrap();
+        let (mut reader, meta) = source.open().unwrap();
+        let mut body = String::new();
+        reader.read_to_string(&mut body).unwrap();
+
+        assert_eq!(body, "id,name\n1,a\n");
+        assert_eq!(meta.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(meta.etag.as_deref(), Some("\"v1\""));
+        let request = server.join().unwrap();
+        assert!(request.iter().any(|line| line.to_lowercase() == "authorization: bearer secret\r\n"));
+    }
+}
@@ -0,0 +1,164 @@
+// Diagnostics go to stderr, so that stdout only carries the output of a command
+// json lines are meant for CI logs
+
+use anyhow::{anyhow, Result};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!("Unknown log format {} (expected text or json)", s)),
+        }
+    }
+}
+
+struct Logger {
+    format: LogFormat,
+    level: LevelFilter,
+    /// Also show info and below from dependencies (http, s3 clients)
+    all_targets: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            && (self.all_targets || metadata.level() <= Level::Warn || metadata.target().starts_with("xtract"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let _ = writeln!(std::io::stderr(), "{}", self.line(record));
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+impl Logger {
+    /// Record as a line of text or a json object
+    ///
+    fn line(&self, record: &Record) -> String {
+        match self.format {
+            LogFormat::Text => format!("[{}] {}", record.level().to_string().to_lowercase(), record.args()),
+            LogFormat::Json => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                json!({
+                    "timestamp": timestamp,
+                    "level": record.level().to_string().to_lowercase(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                })
+                .to_string()
+            }
+        }
+    }
+}
+
+/// Level from the number of -v flags, errors only when quiet
+///
+pub fn level(verbose: u64, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    match verbose {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the logger for the whole process
+///
+pub fn init(verbose: u64, quiet: bool, format: LogFormat) -> Result<()> {
+    let level = level(verbose, quiet);
+    let logger = Logger {
+        format,
+        level,
+        all_targets: verbose >= 2,
+    };
+    log::set_boxed_logger(Box::new(logger))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn logger(format: LogFormat) -> Logger {
+        Logger { format, level: LevelFilter::Info, all_targets: false }
+    }
+
+    #[test]
+    fn level_from_flags() {
+        assert_eq!(level(0, false), LevelFilter::Info);
+        assert_eq!(level(1, false), LevelFilter::Debug);
+        assert_eq!(level(2, false), LevelFilter::Trace);
+        assert_eq!(level(5, false), LevelFilter::Trace);
+        assert_eq!(level(0, true), LevelFilter::Error);
+        assert_eq!(level(2, true), LevelFilter::Error);
+    }
+
+    #[test]
+    fn format_from_name() {
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("JSON".parse::<LogFormat>().is_err());
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn json_and_text_lines() {
+        let lines = |record: &Record| (logger(LogFormat::Json).line(record), logger(LogFormat::Text).line(record));
+        let (json, text) = lines(
+            &Record::builder()
+                .args(format_args!("Read {} rows", 3))
+                .level(Level::Warn)
+                .target("xtract::client")
+                .build(),
+        );
+
+        assert!(!json.contains('\n'));
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["level"], "warn");
+        assert_eq!(value["target"], "xtract::client");
+        assert_eq!(value["message"], "Read 3 rows");
+        assert!(value["timestamp"].as_f64().unwrap() > 0.0);
+
+        assert_eq!(text, "[warn] Read 3 rows");
+    }
+
+    #[test]
+    fn dependencies_only_warn_unless_very_verbose() {
+        let metadata = |level, target| Metadata::builder().level(level).target(target).build();
+        let quiet_deps = logger(LogFormat::Text);
+        assert!(quiet_deps.enabled(&metadata(Level::Info, "xtract::client")));
+        assert!(!quiet_deps.enabled(&metadata(Level::Info, "rusoto_core")));
+        assert!(quiet_deps.enabled(&metadata(Level::Warn, "rusoto_core")));
+        assert!(!quiet_deps.enabled(&metadata(Level::Debug, "xtract::client")));
+
+        let all = Logger { format: LogFormat::Text, level: LevelFilter::Trace, all_targets: true };
+        assert!(all.enabled(&metadata(Level::Trace, "rusoto_core")));
+    }
+}
@@ -1,15 +1,117 @@
 use clap::Clap;
-use anyhow::Result;
+use log::error;
+use std::process;
+use xtract::configuration::ConfigError;
+use xtract::loaders::error::DataFrameError;
 
 pub mod cli;
 pub mod client;
+pub mod explorer;
+pub mod logger;
 // use xtract::{cli, client} ;
 
+use client::ClientError;
 
-fn main() -> Result<()> {
+/// Exit codes, as listed in the README
+const EXIT_EXPECTATIONS_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_CONFIG: i32 = 3;
+const EXIT_DATA: i32 = 4;
+const EXIT_REMOTE: i32 = 5;
+const EXIT_OTHER: i32 = 6;
+
+fn exit_code(error: &anyhow::Error) -> i32 {
+    if let Some(error) = error.downcast_ref::<ClientError>() {
+        return match error {
+            ClientError::ExpectationsFailed(_) => EXIT_EXPECTATIONS_FAILED,
+            ClientError::Usage(_) | ClientError::Unsupported(_) => EXIT_USAGE,
+            ClientError::Remote(_) => EXIT_REMOTE,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<DataFrameError>() {
+        return match error {
+            DataFrameError::StorageError(_) => EXIT_REMOTE,
+            _ => EXIT_DATA,
+        };
+    }
+    if error.is::<ConfigError>() {
+        EXIT_CONFIG
+    } else if error.is::<reqwest::Error>() {
+        EXIT_REMOTE
+    } else if error.is::<std::io::Error>()
+        || error.is::<polars::prelude::PolarsError>()
+        || error.is::<arrow::error::ArrowError>()
+        || error.is::<parquet::errors::ParquetError>()
+    {
+        EXIT_DATA
+    } else {
+        EXIT_OTHER
+    }
+}
+
+/// Error and what to do about it, on stderr through the logger
+///
+fn report(error: &anyhow::Error) {
+    error!("{:#}", error);
+    if let Some(ConfigError::Io { path, .. }) = error.downcast_ref::<ConfigError>() {
+        if path.ends_with(".token") {
+            error!("Please log in first with xtract login");
+        } else {
+            error!("Please create a configuration file under ~/.ncode/configuration.toml (see configuration-sample.toml)");
+        }
+    }
+}
+
+fn main() {
     let args: cli::Args = cli::Args::parse();
+    let format = match args.log_format.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if let Err(e) = logger::init(args.verbose, args.quiet, format) {
+        eprintln!("{}", e);
+        process::exit(EXIT_OTHER);
+    }
+
     let client = client::Frontend::new(args);
-    client.run()
+    if let Err(error) = client.run() {
+        report(&error);
+        process::exit(exit_code(&error));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn exit_code_by_error_kind() {
+        let code = |error: anyhow::Error| exit_code(&error);
+        assert_eq!(code(ClientError::ExpectationsFailed(2).into()), EXIT_EXPECTATIONS_FAILED);
+        assert_eq!(code(ClientError::Usage(String::from("--data or --id")).into()), EXIT_USAGE);
+        assert_eq!(code(ClientError::Unsupported(String::from("set")).into()), EXIT_USAGE);
+        assert_eq!(code(ClientError::Remote(String::from("down")).into()), EXIT_REMOTE);
+        assert_eq!(code(DataFrameError::StorageError(String::from("no bucket")).into()), EXIT_REMOTE);
+        assert_eq!(code(DataFrameError::ParseError(String::from("bad csv")).into()), EXIT_DATA);
+        assert_eq!(code(DataFrameError::ColumnNotFound(String::from("amount")).into()), EXIT_DATA);
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        assert_eq!(code(missing.into()), EXIT_DATA);
+        let config = ConfigError::Io {
+            path: String::from("~/.ncode/.token"),
+            error: std::io::Error::new(std::io::ErrorKind::NotFound, "missing"),
+        };
+        assert_eq!(code(config.into()), EXIT_CONFIG);
+        assert_eq!(code(anyhow!("something else")), EXIT_OTHER);
+
+        // kinds survive added context
+        let wrapped: anyhow::Result<()> = Err(ClientError::Remote(String::from("down")).into());
+        assert_eq!(code(wrapped.context("Publishing profile").unwrap_err()), EXIT_REMOTE);
+    }
 }
 
 
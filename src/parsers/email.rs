@@ -0,0 +1,11 @@
+use regex::Regex;
+use lazy_static::lazy_static;
+
+/// Checks whether address looks like an email address
+pub fn validate_email(address: &str) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"(^[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+$)")
+            .expect("Could not compile regular expression");
+    }
+    RE.is_match(address)
+}
@@ -1 +1,2 @@
+pub mod email;
 pub mod iban;
\ No newline at end of file
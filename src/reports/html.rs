@@ -0,0 +1,255 @@
+// Single file html report, viewable offline: styles and charts (svg) are inline
+
+use crate::loaders::dataframe::{Column, ColumnFeatures, Correlations, DataFrameMeta, Hist};
+use crate::reports::{fmt_number, warnings, ColumnSummary};
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em; color: #222; background: #fafafa; }
+h1 { font-size: 1.6em; } h2 { margin-top: 1.5em; }
+.overview { display: flex; gap: 1em; flex-wrap: wrap; }
+.stat { background: #fff; border: 1px solid #ddd; border-radius: 6px; padding: 0.8em 1.2em; }
+.stat b { display: block; font-size: 1.4em; }
+.cards { display: flex; flex-wrap: wrap; gap: 1em; }
+.card { background: #fff; border: 1px solid #ddd; border-radius: 6px; padding: 1em; width: 360px; }
+.card h3 { margin: 0 0 0.3em 0; word-break: break-all; }
+.dtype { color: #666; font-size: 0.9em; }
+table { border-collapse: collapse; font-size: 0.9em; }
+td, th { padding: 2px 8px; text-align: left; }
+.bar { background: #eee; height: 10px; border-radius: 3px; }
+.bar div { background: #d9534f; height: 10px; border-radius: 3px; }
+.warnings li { color: #a94442; }
+";
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Bar chart of a histogram as inline svg
+///
+fn svg_histogram(hist: &Hist) -> String {
+    let counts = hist.counts();
+    if counts.is_empty() {
+        return String::new();
+    }
+    let (width, height) = (340.0, 90.0);
+    let max = *counts.iter().max().unwrap_or(&1).max(&1) as f64;
+    let bar_width = width / counts.len() as f64;
+
+    let mut svg = format!(r#"<svg width="{}" height="{}" viewBox="0 0 {} {}">"#, width, height + 14.0, width, height + 14.0);
+    for (i, count) in counts.iter().enumerate() {
+        let h = height * *count as f64 / max;
+        svg.push_str(&format!(
+            r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#4a90d9"><title>from {}: {}</title></rect>"##,
+            i as f64 * bar_width + 1.0,
+            height - h,
+            bar_width - 2.0,
+            h,
+            fmt_number(hist.bins().get(i).copied()),
+            count
+        ));
+    }
+    if let (Some(first), Some(last)) = (hist.bins().first(), hist.bins().last()) {
+        svg.push_str(&format!(
+            r#"<text x="0" y="{}" font-size="10">{}</text><text x="{}" y="{}" font-size="10" text-anchor="end">{}</text>"#,
+            height + 12.0,
+            fmt_number(Some(*first)),
+            width,
+            height + 12.0,
+            fmt_number(Some(*last))
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Percentage bar, e.g. for missing values
+///
+fn bar(ratio: f64) -> String {
+    format!(r#"<div class="bar"><div style="width: {:.1}%"></div></div>"#, 100.0 * ratio.max(0.0).min(1.0))
+}
+
+fn column_card(name: &str, column: &Column) -> String {
+    let summary = ColumnSummary::new(name, column);
+    let null_ratio = if column.count() > 0 { column.null_count() as f64 / column.count() as f64 } else { 0.0 };
+
+    let mut card = format!(
+        r#"<div class="card"><h3>{}</h3><div class="dtype">{}{}{}</div>"#,
+        escape(name),
+        summary.dtype,
+        if summary.semantic_type.is_empty() { String::new() } else { format!(" &middot; {}", summary.semantic_type) },
+        if summary.categorical { " &middot; categorical" } else { "" }
+    );
+
+    card.push_str("<table>");
+    card.push_str(&format!("<tr><td>unique</td><td>{}</td></tr>", summary.nunique));
+    card.push_str(&format!(
+        "<tr><td>missing</td><td>{} ({:.1}%)</td></tr></table>{}",
+        summary.null_count,
+        100.0 * null_ratio,
+        bar(null_ratio)
+    ));
+
+    if let ColumnFeatures::Numeric(numeric) = column.features() {
+        card.push_str(&format!(
+            "<table><tr><td>min</td><td>{}</td><td>max</td><td>{}</td></tr><tr><td>mean</td><td>{}</td><td>std</td><td>{}</td></tr></table>",
+            fmt_number(summary.min),
+            fmt_number(summary.max),
+            fmt_number(summary.mean),
+            fmt_number(summary.std)
+        ));
+        if let Some(hist) = numeric.hist() {
+            card.push_str(&svg_histogram(hist));
+        }
+    }
+
+    if let ColumnFeatures::List(list) = column.features() {
+        card.push_str(&format!(
+            "<table><tr><td>min length</td><td>{}</td><td>max length</td><td>{}</td></tr><tr><td>mean length</td><td>{}</td></tr></table>",
+            list.min_len(),
+            list.max_len(),
+            fmt_number(Some(list.mean_len()))
+        ));
+        card.push_str(&svg_histogram(list.lengths()));
+        let kinds: Vec<String> = list.element_types().iter().map(|(k, c)| format!("{} ({})", k, c)).collect();
+        card.push_str(&format!("<div class=\"dtype\">elements: {}</div>", escape(&kinds.join(", "))));
+    }
+
+    if !column.types().is_empty() {
+        let total: usize = column.types().values().sum();
+        card.push_str("<h4>Detected types</h4><table>");
+        for (t, count) in column.types().iter() {
+            card.push_str(&format!(
+                "<tr><td>{:?}</td><td>{}</td><td style=\"width: 150px\">{}</td></tr>",
+                t,
+                count,
+                bar(*count as f64 / total.max(1) as f64)
+            ));
+        }
+        card.push_str("</table>");
+    }
+
+    if !column.top_values().is_empty() {
+        card.push_str("<h4>Top values</h4><table>");
+        for value in column.top_values().iter().take(10) {
+            card.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", escape(&value.value), value.count));
+        }
+        card.push_str("</table>");
+    }
+
+    card.push_str("</div>");
+    card
+}
+
+/// Correlation matrix as a grid of colored cells (blue negative, red positive)
+///
+fn svg_heatmap(correlations: &Correlations) -> String {
+    let n = correlations.columns.len();
+    let cell = 28.0;
+    let label = 140.0;
+    let size = label + cell * n as f64;
+
+    let mut svg = format!(r#"<svg width="{}" height="{}">"#, size, size);
+    for (i, name) in correlations.columns.iter().enumerate() {
+        let offset = label + cell * i as f64 + cell / 2.0;
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" font-size="11" text-anchor="end">{}</text>"#,
+            label - 4.0,
+            offset + 4.0,
+            escape(name)
+        ));
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" font-size="11" transform="rotate(-90 {} {})">{}</text>"#,
+            offset + 4.0,
+            label - 4.0,
+            offset + 4.0,
+            label - 4.0,
+            escape(name)
+        ));
+    }
+    for (i, row) in correlations.values.iter().enumerate() {
+        for (j, r) in row.iter().enumerate() {
+            let x = label + cell * j as f64;
+            let y = label + cell * i as f64;
+            let r = match r {
+                Some(r) => *r,
+                None => {
+                    // undefined, e.g. a constant column: empty cell
+                    svg.push_str(&format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="rgb(220,220,220)"><title>{} / {}</title></rect>"#,
+                        x,
+                        y,
+                        cell - 1.0,
+                        cell - 1.0,
+                        escape(&correlations.columns[i]),
+                        escape(&correlations.columns[j])
+                    ));
+                    continue;
+                }
+            };
+            let (red, blue) = if r >= 0.0 {
+                (255, (255.0 * (1.0 - r)) as u8)
+            } else {
+                ((255.0 * (1.0 + r)) as u8, 255)
+            };
+            let green = red.min(blue);
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"><title>{} / {}: {:.3}</title></rect>"#,
+                x,
+                y,
+                cell - 1.0,
+                cell - 1.0,
+                red,
+                green,
+                blue,
+                escape(&correlations.columns[i]),
+                escape(&correlations.columns[j]),
+                r
+            ));
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+pub fn render(profile: &DataFrameMeta) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+    html.push_str(&format!("<title>Profile of {}</title>", escape(profile.datasource())));
+    html.push_str(&format!("<style>{}</style></head><body>", STYLE));
+
+    html.push_str(&format!("<h1>Profile of {}</h1>", escape(profile.datasource())));
+    let missing: usize = profile.columns().map(|(_, c)| c.null_count()).sum();
+    html.push_str(&format!(
+        r#"<div class="overview"><div class="stat"><b>{}</b>rows</div><div class="stat"><b>{}</b>columns</div><div class="stat"><b>{}</b>missing cells</div><div class="stat"><b>{}</b>data id</div></div>"#,
+        profile.nrows(),
+        profile.ncols(),
+        missing,
+        escape(&profile.data_id())
+    ));
+
+    let warnings = warnings(profile);
+    if !warnings.is_empty() {
+        html.push_str("<h2>Warnings</h2><ul class=\"warnings\">");
+        for (column, message) in warnings.iter() {
+            html.push_str(&format!("<li><b>{}</b>: {}</li>", escape(column), escape(message)));
+        }
+        html.push_str("</ul>");
+    }
+
+    html.push_str("<h2>Columns</h2><div class=\"cards\">");
+    for (name, column) in profile.columns() {
+        html.push_str(&column_card(name, column));
+    }
+    html.push_str("</div>");
+
+    if let Some(correlations) = profile.correlations() {
+        html.push_str("<h2>Correlations</h2>");
+        html.push_str(&svg_heatmap(correlations));
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
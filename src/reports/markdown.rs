@@ -0,0 +1,91 @@
+use crate::loaders::dataframe::{ColumnFeatures, DataFrameMeta};
+use crate::reports::{fmt_number, ColumnSummary};
+
+/// Escape pipes so that values do not break table cells
+///
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+pub fn render(profile: &DataFrameMeta) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("# Profile of {}\n\n", cell(profile.datasource())));
+    out.push_str(&format!("- rows: {}\n- columns: {}\n- data id: {}\n\n", profile.nrows(), profile.ncols(), profile.data_id()));
+
+    out.push_str("## Columns\n\n");
+    out.push_str("| column | type | semantic type | nulls | unique | categorical | min | max | mean | top value |\n");
+    out.push_str("|---|---|---|---:|---:|---|---:|---:|---:|---|\n");
+    for (name, column) in profile.columns() {
+        let summary = ColumnSummary::new(name, column);
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            cell(&summary.name),
+            summary.dtype,
+            summary.semantic_type,
+            summary.null_count,
+            summary.nunique,
+            if summary.categorical { "yes" } else { "no" },
+            fmt_number(summary.min),
+            fmt_number(summary.max),
+            fmt_number(summary.mean),
+            cell(&summary.top_value.unwrap_or_default()),
+        ));
+    }
+
+    // details only for columns that have something to say
+    for (name, column) in profile.columns() {
+        let mut details: Vec<String> = vec![];
+
+        if !column.null_tokens().is_empty() {
+            let tokens: Vec<String> = column
+                .null_tokens()
+                .iter()
+                .map(|(token, count)| format!("`{}` ({})", token, count))
+                .collect();
+            details.push(format!("- null tokens: {}", tokens.join(", ")));
+        }
+
+        if let ColumnFeatures::Numeric(numeric) = column.features() {
+            if let Some(outliers) = numeric.outliers() {
+                if outliers.zscore + outliers.modified_zscore + outliers.iqr > 0 {
+                    details.push(format!(
+                        "- outliers: {} by z-score, {} by modified z-score, {} outside IQR fences [{}, {}]",
+                        outliers.zscore,
+                        outliers.modified_zscore,
+                        outliers.iqr,
+                        fmt_number(Some(outliers.lower_fence)),
+                        fmt_number(Some(outliers.upper_fence)),
+                    ));
+                }
+            }
+        }
+
+        if let ColumnFeatures::List(list) = column.features() {
+            let kinds: Vec<String> = list.element_types().iter().map(|(k, c)| format!("{} ({})", k, c)).collect();
+            details.push(format!(
+                "- list length: min {}, max {}, mean {}; elements: {}",
+                list.min_len(),
+                list.max_len(),
+                fmt_number(Some(list.mean_len())),
+                kinds.join(", ")
+            ));
+        }
+
+        if column.top_values().len() > 1 {
+            let values: Vec<String> = column
+                .top_values()
+                .iter()
+                .take(5)
+                .map(|v| format!("`{}` ({})", cell(&v.value), v.count))
+                .collect();
+            details.push(format!("- top values: {}", values.join(", ")));
+        }
+
+        if !details.is_empty() {
+            out.push_str(&format!("\n### {}\n\n{}\n", cell(name), details.join("\n")));
+        }
+    }
+
+    out
+}
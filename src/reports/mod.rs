@@ -0,0 +1,287 @@
+// Rendering of a profile (DataFrameMeta) into the supported output formats
+
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+use crate::expectations::generate::dominant_semantic_type;
+use crate::expectations::validate::type_name;
+use crate::loaders::dataframe::{Column, ColumnFeatures, DataFrameMeta};
+
+pub mod html;
+pub mod markdown;
+pub mod terminal;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    JsonPretty,
+    Yaml,
+    Markdown,
+    CsvSummary,
+    Html,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "json-pretty" => Ok(OutputFormat::JsonPretty),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "csv-summary" => Ok(OutputFormat::CsvSummary),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(anyhow!(
+                "Unknown format {} (expected json, json-pretty, yaml, markdown, csv-summary or html)",
+                s
+            )),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Guess format from the extension of an output path
+    ///
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit('.').next()?;
+        match extension {
+            "json" => Some(OutputFormat::JsonPretty),
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "md" => Some(OutputFormat::Markdown),
+            "csv" => Some(OutputFormat::CsvSummary),
+            "html" | "htm" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Json | OutputFormat::JsonPretty => "application/json",
+            OutputFormat::Yaml => "application/x-yaml",
+            OutputFormat::Markdown => "text/markdown",
+            OutputFormat::CsvSummary => "text/csv",
+            OutputFormat::Html => "text/html",
+        }
+    }
+}
+
+/// One line summary of a column, shared by the tabular formats
+///
+pub struct ColumnSummary {
+    pub name: String,
+    pub dtype: String,
+    pub semantic_type: String,
+    pub null_count: usize,
+    pub nunique: usize,
+    pub categorical: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub std: Option<f64>,
+    pub top_value: Option<String>,
+}
+
+impl ColumnSummary {
+    pub fn new(name: &str, column: &Column) -> Self {
+        let (min, max, mean, std) = match column.features() {
+            ColumnFeatures::Numeric(numeric) => (
+                numeric.min(),
+                numeric.max(),
+                numeric.mean(),
+                numeric.std(),
+            ),
+            _ => (None, None, None, None),
+        };
+
+        ColumnSummary {
+            name: name.to_string(),
+            dtype: match column.features() {
+                ColumnFeatures::List(_) => "List",
+                _ => column.dtype().map(|t| type_name(t)).unwrap_or("Unknown"),
+            }
+            .to_string(),
+            semantic_type: dominant_semantic_type(column)
+                .map(|(t, _)| format!("{:?}", t))
+                .unwrap_or_default(),
+            null_count: column.null_count(),
+            nunique: column.nunique(),
+            categorical: column.is_categorical(),
+            min,
+            max,
+            mean,
+            std,
+            top_value: column.top_values().first().map(|v| v.value.clone()),
+        }
+    }
+}
+
+/// Format an optional number for tables, empty if missing
+///
+pub fn fmt_number(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.fract() == 0.0 && v.abs() < 1e15 => format!("{}", v as i64),
+        Some(v) => format!("{:.4}", v),
+        None => String::new(),
+    }
+}
+
+/// Issues worth the attention of whoever reads the profile, as (column, message)
+///
+pub fn warnings(profile: &DataFrameMeta) -> Vec<(String, String)> {
+    let mut warnings: Vec<(String, String)> = vec![];
+
+    for (name, column) in profile.columns() {
+        let mut warn = |message: String| warnings.push((name.clone(), message));
+
+        if column.count() > 0 && column.null_count() == column.count() {
+            warn(String::from("all values are missing"));
+        } else if column.count() > 0 && column.null_count() as f64 / column.count() as f64 > 0.2 {
+            warn(format!(
+                "{:.1}% of values are missing",
+                100.0 * column.null_count() as f64 / column.count() as f64
+            ));
+        }
+
+        if let Some(present) = column.present() {
+            if present < profile.nrows() {
+                warn(format!("present in {} of {} records", present, profile.nrows()));
+            }
+        }
+
+        if column.nunique() == 1 {
+            warn(String::from("constant column"));
+        }
+
+        if !column.null_tokens().is_empty() {
+            let tokens: Vec<&str> = column.null_tokens().keys().map(|t| t.as_str()).collect();
+            warn(format!("null tokens found: {:?}", tokens));
+        }
+
+        if let ColumnFeatures::Numeric(numeric) = column.features() {
+            if let Some(outliers) = numeric.outliers() {
+                if outliers.iqr > 0 {
+                    warn(format!("{} value(s) outside IQR fences", outliers.iqr));
+                }
+            }
+        }
+    }
+
+    if let Some(correlations) = profile.correlations() {
+        for (i, a) in correlations.columns.iter().enumerate() {
+            for (j, b) in correlations.columns.iter().enumerate().skip(i + 1) {
+                if let Some(r) = correlations.values[i][j].filter(|r| r.abs() > 0.95) {
+                    warnings.push((a.clone(), format!("highly correlated with {} ({:.3})", b, r)));
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+fn render_csv_summary(profile: &DataFrameMeta) -> Result<String> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    wtr.write_record(&[
+        "column", "dtype", "semantic_type", "null_count", "nunique", "categorical", "min", "max", "mean", "std",
+        "top_value",
+    ])?;
+
+    for (name, column) in profile.columns() {
+        let summary = ColumnSummary::new(name, column);
+        wtr.write_record(&[
+            summary.name,
+            summary.dtype,
+            summary.semantic_type,
+            summary.null_count.to_string(),
+            summary.nunique.to_string(),
+            summary.categorical.to_string(),
+            fmt_number(summary.min),
+            fmt_number(summary.max),
+            fmt_number(summary.mean),
+            fmt_number(summary.std),
+            summary.top_value.unwrap_or_default(),
+        ])?;
+    }
+
+    let bytes = wtr.into_inner().map_err(|e| anyhow!("{}", e))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Render profile in format
+///
+pub fn render(profile: &DataFrameMeta, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string(profile)?),
+        OutputFormat::JsonPretty => Ok(serde_json::to_string_pretty(profile)?),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(profile)?),
+        OutputFormat::Markdown => Ok(markdown::render(profile)),
+        OutputFormat::CsvSummary => render_csv_summary(profile),
+        OutputFormat::Html => Ok(html::render(profile)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaders::dataframe::NcodeDataFrame;
+    use polars::prelude::{DataFrame, Series};
+
+    fn profile() -> DataFrameMeta {
+        let columns = vec![
+            Series::new("name", &[Some("a"), Some("b"), None]),
+            Series::new("amount", &[1.5, 2.0, 2.5]),
+        ];
+        let mut profile = NcodeDataFrame::new(DataFrame::new(columns).unwrap())
+            .with_progress(false)
+            .profile()
+            .unwrap();
+        profile.set_datasource(String::from("data.csv"));
+        profile
+    }
+
+    #[test]
+    fn format_from_path_and_name() {
+        assert_eq!(OutputFormat::from_path("out/profile.json"), Some(OutputFormat::JsonPretty));
+        assert_eq!(OutputFormat::from_path("profile.yml"), Some(OutputFormat::Yaml));
+        assert_eq!(OutputFormat::from_path("profile.md"), Some(OutputFormat::Markdown));
+        assert_eq!(OutputFormat::from_path("summary.csv"), Some(OutputFormat::CsvSummary));
+        assert_eq!(OutputFormat::from_path("report.htm"), Some(OutputFormat::Html));
+        assert_eq!(OutputFormat::from_path("profile.txt"), None);
+        assert_eq!(OutputFormat::from_path("profile"), None);
+        assert_eq!("json-pretty".parse::<OutputFormat>().unwrap(), OutputFormat::JsonPretty);
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn format_numbers() {
+        assert_eq!(fmt_number(Some(3.0)), "3");
+        assert_eq!(fmt_number(Some(-2.0)), "-2");
+        assert_eq!(fmt_number(Some(1.0 / 3.0)), "0.3333");
+        assert_eq!(fmt_number(Some(1e16)), "10000000000000000.0000");
+        assert_eq!(fmt_number(None), "");
+    }
+
+    #[test]
+    fn render_every_format() {
+        let profile = profile();
+
+        let json = render(&profile, OutputFormat::Json).unwrap();
+        let reloaded: DataFrameMeta = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.get_column_names(), vec!["name", "amount"]);
+        assert!(render(&profile, OutputFormat::JsonPretty).unwrap().contains('\n'));
+        assert!(render(&profile, OutputFormat::Yaml).unwrap().contains("datasource: data.csv"));
+
+        let csv = render(&profile, OutputFormat::CsvSummary).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("column,dtype,semantic_type"));
+        assert!(lines[1].starts_with("name,Str,"));
+        assert!(lines[2].starts_with("amount,Float,"));
+
+        for format in &[OutputFormat::Markdown, OutputFormat::Html] {
+            let content = render(&profile, *format).unwrap();
+            assert!(content.contains("amount") && content.contains("data.csv"));
+        }
+    }
+}
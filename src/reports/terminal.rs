@@ -0,0 +1,185 @@
+// Compact summary of a profile for the terminal
+
+use crate::loaders::dataframe::{ColumnFeatures, DataFrameMeta, Hist};
+use crate::reports::{fmt_number, ColumnSummary};
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_SPARKS: [char; 8] = ['_', '.', ':', '-', '=', '+', '*', '#'];
+
+/// Width of the terminal attached to stdout, 80 if unknown
+///
+pub fn terminal_width() -> usize {
+    match terminal_size::terminal_size() {
+        Some((terminal_size::Width(w), _)) => w as usize,
+        None => std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(80),
+    }
+}
+
+/// Cut value to width chars, marking the cut with ~
+///
+fn fit(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        format!("{:width$}", value, width = width)
+    } else if width == 0 {
+        String::new()
+    } else {
+        let cut: String = value.chars().take(width - 1).collect();
+        format!("{}~", cut)
+    }
+}
+
+/// Width characters, bins are merged or repeated to fill them
+///
+pub fn sparkline(hist: &Hist, width: usize, unicode: bool) -> String {
+    let counts = hist.counts();
+    if counts.is_empty() || width == 0 {
+        return String::new();
+    }
+    let sparks = if unicode { &SPARKS } else { &ASCII_SPARKS };
+
+    let resampled: Vec<u64> = (0..width)
+        .map(|i| {
+            let start = i * counts.len() / width;
+            let end = ((i + 1) * counts.len() / width).max(start + 1);
+            counts[start..end].iter().sum()
+        })
+        .collect();
+    let max = *resampled.iter().max().unwrap_or(&0);
+
+    resampled
+        .iter()
+        .map(|c| {
+            if max == 0 || *c == 0 {
+                ' '
+            } else {
+                sparks[((*c as f64 / max as f64) * (sparks.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Table of columns with a histogram of numeric ones, fitted to width
+/// Plain ascii is used when unicode is false (e.g. output is not a terminal)
+///
+pub fn render(profile: &DataFrameMeta, width: usize, unicode: bool) -> String {
+    let mut out = format!(
+        "{}: {} rows x {} columns\n\n",
+        profile.datasource(),
+        profile.nrows(),
+        profile.ncols()
+    );
+
+    let summaries: Vec<ColumnSummary> = profile.columns().map(|(n, c)| ColumnSummary::new(n, c)).collect();
+    let name_width = summaries.iter().map(|s| s.name.chars().count()).max().unwrap_or(6).max(6).min(24);
+    // column, dtype, semantic, nulls, unique are fixed, the rest of the line is for values
+    let fixed = name_width + 6 + 9 + 9 + 9 + 5;
+    let values_width = width.saturating_sub(fixed).max(10);
+
+    out.push_str(&format!(
+        "{} {} {} {:>8} {:>8}  {}\n",
+        fit("column", name_width),
+        fit("dtype", 5),
+        fit("semantic", 8),
+        "nulls",
+        "unique",
+        fit("min / max or top value", values_width).trim_end()
+    ));
+    out.push_str(&format!("{}\n", "-".repeat((fixed + values_width).min(width))));
+
+    for summary in summaries.iter() {
+        let values = match (summary.min, summary.max) {
+            (Some(_), Some(_)) => format!("{} / {}", fmt_number(summary.min), fmt_number(summary.max)),
+            _ => summary.top_value.clone().unwrap_or_default(),
+        };
+        out.push_str(&format!(
+            "{} {} {} {:>8} {:>8}  {}\n",
+            fit(&summary.name, name_width),
+            fit(&summary.dtype, 5),
+            fit(&summary.semantic_type, 8),
+            summary.null_count,
+            summary.nunique,
+            fit(&values, values_width).trim_end()
+        ));
+    }
+
+    let hist_width = width.saturating_sub(name_width + 3).max(10);
+    let mut histograms = String::new();
+    for (name, column) in profile.columns() {
+        if let ColumnFeatures::Numeric(numeric) = column.features() {
+            if let Some(hist) = numeric.hist() {
+                histograms.push_str(&format!(
+                    "{} |{}|\n",
+                    fit(name, name_width),
+                    sparkline(hist, hist_width, unicode)
+                ));
+            }
+        }
+    }
+    if !histograms.is_empty() {
+        out.push_str("\nHistograms\n");
+        out.push_str(&histograms);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaders::dataframe::NcodeDataFrame;
+    use polars::prelude::{DataFrame, Series};
+
+    fn hist(counts: Vec<u64>) -> Hist {
+        let mut hist = Hist::new();
+        hist.update((0..counts.len()).map(|b| b as f64).collect(), counts);
+        hist
+    }
+
+    #[test]
+    fn fit_pads_or_cuts() {
+        assert_eq!(fit("id", 4), "id  ");
+        assert_eq!(fit("amount", 6), "amount");
+        assert_eq!(fit("amount", 4), "amo~");
+        assert_eq!(fit("é€ab", 3), "é€~");
+        assert_eq!(fit("amount", 0), "");
+    }
+
+    #[test]
+    fn sparkline_fills_width() {
+        let hist = hist(vec![0, 1, 2, 4]);
+        assert_eq!(sparkline(&hist, 4, false), " :=#");
+        assert_eq!(sparkline(&hist, 4, true), " ▃▅█");
+        // bins are repeated on wide terminals and merged on narrow ones
+        assert_eq!(sparkline(&hist, 8, false), "  ::==##");
+        assert_eq!(sparkline(&hist, 2, false), ".#");
+        assert_eq!(sparkline(&hist, 0, false), "");
+        assert_eq!(sparkline(&Hist::new(), 10, false), "");
+    }
+
+    #[test]
+    fn render_fitted_to_width() {
+        let columns = vec![
+            Series::new("a_very_long_column_name_to_cut", &["x", "y", "x"]),
+            Series::new("amount", &[1.0, 2.0, 40.0]),
+        ];
+        let mut profile = NcodeDataFrame::new(DataFrame::new(columns).unwrap())
+            .with_progress(false)
+            .profile()
+            .unwrap();
+        profile.set_datasource(String::from("data.csv"));
+
+        let narrow = render(&profile, 80, false);
+        assert!(narrow.starts_with("data.csv: 3 rows x 2 columns"));
+        assert!(narrow.contains("a_very_long_column_name~ "));
+        assert!(narrow.lines().all(|line| line.chars().count() <= 80), "{}", narrow);
+        assert!(narrow.is_ascii());
+
+        let wide = render(&profile, 120, true);
+        let sparkline = wide.lines().last().unwrap();
+        assert!(sparkline.starts_with("amount"));
+        assert_eq!(sparkline.chars().count(), 120);
+    }
+}
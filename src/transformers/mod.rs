@@ -1 +1 @@
-pub mod simple;
\ No newline at end of file
+pub mod simple;
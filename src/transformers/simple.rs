@@ -2,6 +2,7 @@
 
 use polars::prelude::*;
 use crate::loaders::dataframe::DataFrameMeta;
+use crate::loaders::error::{DataFrameError, Result as FrameResult};
 
 
 pub struct Transformer {
@@ -17,7 +18,7 @@ impl Transformer {
         }
     }
 
-    pub fn transform(&self) -> DataFrame {
+    pub fn transform(&self) -> FrameResult<DataFrame> {
 
         // assert dataframe is consistent with profile from colnames
         let data_cols = self.dataframe.get_column_names();
@@ -26,17 +27,19 @@ impl Transformer {
         for col in data_cols.clone() {
             let col = col.to_string();
             let exists = profile_cols.iter().any(|i| i == &col.clone());
-            assert!(exists, "Data and profile do not match");
+            if !exists {
+                return Err(DataFrameError::ComputeError(format!("Data and profile do not match: no column {} in profile", col)));
+            }
         }
 
         // perform column transformation using profile
         let mut transformed_cols: Vec<Series> = vec![];
         for col in data_cols.clone() {
-            let colvalues = self.dataframe.column(col).unwrap().to_owned();
+            let colvalues = self.dataframe.column(col)?.to_owned();
             transformed_cols.push(colvalues);
         }
 
-        let df = DataFrame::new(transformed_cols).unwrap();
-        df
+        let df = DataFrame::new(transformed_cols)?;
+        Ok(df)
     }
 }
\ No newline at end of file